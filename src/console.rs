@@ -4,7 +4,7 @@
 use std::io;
 use std::collections::HashSet;
 
-use crate::game::{Player, Choice, Score};
+use crate::game::{Player, Choice, Score, Action};
 use crate::session::{Progression, Session};
 
 pub fn play_session(mut session: Session) {
@@ -19,12 +19,27 @@ pub fn play_session(mut session: Session) {
         // 2. Check if we game on.
         match state.game() {
             Progression::PlayOn(outcome) => println!("{}", &outcome),
-            Progression::GameOverWinner(player) => {
-                println!("Game Over\nWinner is {}", &player);
+            Progression::GameOverWinner(winner) => {
+                println!("Game Over\nWinner is {}", &winner);
+                if let Some(results) = session.results() {
+                    println!("Placings: {}", results.placings());
+                }
+                break;
+            },
+            Progression::GameOverStalemate(ranking) => {
+                match ranking.winner() {
+                    Some(player) => println!("Game Over\nWinner on points is {}", &player),
+                    None => println!("Game Over\nSTALEMATE shared on points"),
+                }
+                println!("Placings: {}", &ranking);
                 break;
             },
-            Progression::GameOverStalemate(players) => {
-                println!("Game Over\nSTATELMATE between players {:?}", &players);
+            Progression::GameOverRoundLimit(ranking) => {
+                println!("Game Over\nRound limit reached. Placings: {}", &ranking);
+                break;
+            },
+            Progression::GameOverVictoryPoints(winner) => {
+                println!("Game Over\nWinner on victory points is {}", &winner);
                 break;
             },
         }
@@ -33,6 +48,9 @@ pub fn play_session(mut session: Session) {
         let available_choices = state.choices();
 
         if let Some(index) = handle_player_turn_input(available_choices.as_slice()) {
+            if *available_choices[index].action() == Action::Pass {
+                println!("Player {} ends their turn.", state.board().players().current());
+            }
             session.advance(index).unwrap();
         } else {
             println!("Quitting game. No Winner.");
@@ -56,12 +74,27 @@ pub fn play_session_with_ai(
         // 2. Check if we game on.
         match state.game() {
             Progression::PlayOn(outcome) => println!("{}", &outcome),
-            Progression::GameOverWinner(player) => {
-                println!("Game Over\nWinner is {}", &player);
+            Progression::GameOverWinner(winner) => {
+                println!("Game Over\nWinner is {}", &winner);
+                if let Some(results) = session.results() {
+                    println!("Placings: {}", results.placings());
+                }
+                break;
+            },
+            Progression::GameOverStalemate(ranking) => {
+                match ranking.winner() {
+                    Some(player) => println!("Game Over\nWinner on points is {}", &player),
+                    None => println!("Game Over\nSTALEMATE shared on points"),
+                }
+                println!("Placings: {}", &ranking);
+                break;
+            },
+            Progression::GameOverRoundLimit(ranking) => {
+                println!("Game Over\nRound limit reached. Placings: {}", &ranking);
                 break;
             },
-            Progression::GameOverStalemate(players) => {
-                println!("Game Over\nSTATELMATE between players {:?}", &players);
+            Progression::GameOverVictoryPoints(winner) => {
+                println!("Game Over\nWinner on victory points is {}", &winner);
                 break;
             },
         }
@@ -131,10 +164,12 @@ pub fn handle_player_turn_input(choices: &[Choice]) -> Option<usize> {
 pub fn print_actions_from_choices(choices: &[Choice]) {
     choices
         .iter()
-        .map(|c| c.action())
         .enumerate()
-        .for_each(|(num, act)| {
-            println!("{}. {}", num + 1, act);
+        .for_each(|(num, choice)| match choice.odds() {
+            Some(odds) => println!(
+                "{}. {} Your odds: {:.0}%", num + 1, choice.action(), odds * 100_f64,
+            ),
+            None => println!("{}. {}", num + 1, choice.action()),
         }); 
 }
 
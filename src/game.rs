@@ -2,17 +2,33 @@
 use std::iter::Iterator;
 
 use rand::prelude::*;
+use rand::distributions::WeightedIndex;
 
 use crate::hexagon::{Rectangular, Grid, Cube};
 
 pub mod player;
 pub mod model;
+pub mod probability;
 mod generate;
 mod rules;
+mod ranking;
+mod ruleset;
 mod score;
 
-pub use model::{Board, Tree, Choice, Action, Consequence, Score, Holding};
+pub use model::{
+    Board, BoardView, Sighting, Tree, Choice, Chance, Action, Consequence, Score, Holding,
+    Terrain,
+};
 pub use player::{Player, Players};
+pub use ranking::Ranking;
+pub use rules::{
+    board_from_failed_attack, board_from_pass, board_from_resign, rank_players,
+    defence_bonus, points_leader,
+};
+pub use ruleset::{
+    Ruleset, PassRule, FailurePenalty, AttackRule, Reinforcement, Placement, TieRule, Die,
+    TieBreaker, CapitalLoss, Handicap, Resignation,
+};
 pub use generate::{
     start_tree_horizon_limited,
     start_tree_insert_budgeted,
@@ -22,17 +38,114 @@ pub use generate::{
 pub use score::{score_tree, clear_all_scoring, clear_scoring_from, score_tree_from};
 use model::Hold;
 
-pub fn generate_random_grid(columns: u32, rows: u32, players: Players) -> Grid<u8> {
-    let mut rng = thread_rng();
-    let grid: Grid<u8> = Rectangular::generate(columns, rows, 0).into();
+pub fn generate_random_grid<H: Holding>(
+    columns: u32, rows: u32, players: Players,
+) -> Grid<H> {
+    generate_random_grid_with_neutrals(columns, rows, players, 0_f64)
+}
+
+/// Like `generate_random_grid` but each hex has a `neutral` chance, from 0 to 1, of
+/// belonging to nobody.
+pub fn generate_random_grid_with_neutrals<H: Holding>(
+    columns: u32, rows: u32, players: Players, neutral: f64,
+) -> Grid<H> {
+    generate_handicapped_grid(
+        columns, rows, players, neutral, &Ruleset::default(), &mut thread_rng(),
+    )
+}
+
+/// Like `generate_random_grid_with_neutrals` but each player's starting hexes and dice
+/// follow their `Handicap` in the `ruleset`. The hexes are dealt out using `rng` so a
+/// seeded one always gives the same grid.
+pub fn generate_handicapped_grid<H: Holding, R: Rng + ?Sized>(
+    columns: u32, rows: u32, players: Players, neutral: f64, ruleset: &Ruleset, rng: &mut R,
+) -> Grid<H> {
+    let blank = H::new(Player::neutral(), 0, true);
+    let grid: Grid<H> = Rectangular::generate(columns, rows, blank).into();
+    populate_handicapped_grid(&grid, players, neutral, ruleset, rng)
+}
 
-    grid.fork_with(move |_,_| {
+/// Randomly hand out the hexes of a map made beforehand, such as one with fortress and
+/// marsh hexes of their own capacity. Everything about each hex other than the owner and
+/// dice is kept and no hex is given more dice than its capacity. Each hex has a `neutral`
+/// chance, from 0 to 1, of belonging to nobody. Water always belongs to nobody.
+pub fn populate_grid<H: Holding>(
+    grid: &Grid<H>, players: Players, neutral: f64,
+) -> Grid<H> {
+    populate_handicapped_grid(
+        grid, players, neutral, &Ruleset::default(), &mut thread_rng(),
+    )
+}
+
+/// Like `populate_grid` but each player's `Handicap` in the `ruleset` is applied. Owners
+/// are drawn by their share of the hexes and each player's extra dice then go one at a
+/// time onto a random hex of theirs with room left. All the randomness comes from `rng`.
+/// Panics if there are more `players` than the `Holding` can store.
+pub fn populate_handicapped_grid<H: Holding, R: Rng + ?Sized>(
+    grid: &Grid<H>, players: Players, neutral: f64, ruleset: &Ruleset, rng: &mut R,
+) -> Grid<H> {
+    assert!(players.total() <= H::PLAYER_LIMIT, "Too many players for the holding.");
+    let playing = players.playing();
+    let shares = WeightedIndex::new(
+        playing
+            .iter()
+            .map(|player| 1 + *ruleset.handicap(*player).hexes() as u32)
+    ).expect("There is always at least one player.");
+
+    let mut grid = grid.fork_with(|_, hold| {
+        // Water belongs to nobody.
+        if !hold.terrain().passable() {
+            return hold.update(Player::neutral(), 0, true);
+        }
         let player_dice = rng.gen_range(1, 6);
-        u8::new(players.sample(&mut rng), player_dice, true)
-    })
+        let player_dice = match hold.capacity() {
+            Some(capacity) => player_dice.min(capacity.max(1)),
+            None => player_dice,
+        };
+        let owner = if rng.gen_bool(neutral.clamp(0_f64, 1_f64)) {
+            Player::neutral()
+        } else {
+            playing[shares.sample(rng)]
+        };
+        hold.update(owner, player_dice, true)
+    });
+
+    let max_dice = (*ruleset.max_dice()).min(H::DICE_LIMIT);
+    for player in playing.iter().cloned() {
+        for _ in 0..*ruleset.handicap(player).dice() {
+            let open: Vec<Cube> = grid
+                .iter()
+                .filter(|ht| {
+                    let hold = ht.data();
+                    let capacity = hold.capacity().unwrap_or(max_dice).min(H::DICE_LIMIT);
+                    hold.owner() == player
+                        && hold.terrain().passable()
+                        && hold.dice() < capacity
+                })
+                .map(|ht| *ht.coordinate())
+                .collect();
+
+            if open.is_empty() {
+                break;
+            }
+
+            let target = open[rng.gen_range(0, open.len())];
+            grid = grid.fork_with(|cube, hold| {
+                if *cube == target {
+                    hold.update(player, hold.dice() + 1, hold.mobile())
+                } else {
+                    hold
+                }
+            });
+        }
+    }
+
+    grid
 }
 
-pub fn generate_random_board(columns: u32, rows: u32, players: Players) -> Board {
+pub fn generate_random_board<H: Holding>(
+    columns: u32, rows: u32, players: Players,
+) -> Board<H> {
     let grid = generate_random_grid(columns, rows, players);
     Board::new(players, grid, 0, 0)
 }
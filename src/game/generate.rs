@@ -2,21 +2,25 @@
 use std::collections::HashMap;
 
 use super::model::*;
-use super::rules::choices_from_board_only_pass_at_end;
+use super::rules::choices_from_board;
+use super::ruleset::Ruleset;
+
+/// Every board generated so far with the choices that can be made from it.
+type States<H> = HashMap<Board<H>, Vec<Choice<H>>>;
 
 /// Attemps construction of the entire tree. Can choke on 3x3 boards and will definitiely
 /// OOM on 4x4 boards and above.
-pub fn build_tree(root: Board, move_limit: u8) -> Tree {
-    let states = calculate_all_consequences(root.clone(), move_limit);
+pub fn build_tree<H: Holding>(root: Board<H>, ruleset: &Ruleset) -> Tree<H> {
+    let states = calculate_all_consequences(root.clone(), ruleset);
     Tree::new(root, states)
 }
 
 /// Like above using brute force calculation to evaluate all board positions. But will stop
 /// at the depth indicated by `horizon`.
-pub fn start_tree_horizon_limited(
-    root: Board, horizon: usize, move_limit: u8
-) -> Tree {
-    let states = calculate_consequences(root.clone(), horizon, move_limit);
+pub fn start_tree_horizon_limited<H: Holding>(
+    root: Board<H>, horizon: usize, ruleset: &Ruleset,
+) -> Tree<H> {
+    let states = calculate_consequences(root.clone(), horizon, ruleset);
     Tree::new(root, states)
 }
 
@@ -26,23 +30,23 @@ pub fn start_tree_horizon_limited(
 ///
 /// **NOTE**, the first layer will always be computed otherwise valid choices from the
 /// start will be denied to the player. This is only an issue on insane 100x100 boards.
-pub fn start_tree_insert_budgeted(
-    root: Board, board_budget: usize, move_limit: u8,
-) -> Tree {
+pub fn start_tree_insert_budgeted<H: Holding>(
+    root: Board<H>, board_budget: usize, ruleset: &Ruleset,
+) -> Tree<H> {
     let states = calculate_consequences_insert_limited(
-        root.clone(), board_budget, move_limit,
+        root.clone(), board_budget, ruleset,
     );
     Tree::new(root, states)
 }
 
 /// Adds to the sent tree. If the `Board` is not within the tree, it is returned as Err.
-pub fn grow_tree_horizon_limited(
-    from: Board, horizon: usize, tree: &mut Tree, move_limit: u8,
-) -> Result<(), Board> {
-    let _ = tree.fetch_choices(&from).ok_or_else(|| from.clone());
+pub fn grow_tree_horizon_limited<H: Holding>(
+    from: Board<H>, horizon: usize, tree: &mut Tree<H>, ruleset: &Ruleset,
+) -> Result<(), Box<Board<H>>> {
+    let _ = tree.fetch_choices(&from).ok_or_else(|| Box::new(from.clone()));
 
     // Fairly wasteful as many positions already calculated will be re-calculated.
-    let new_states = calculate_consequences(from, horizon, move_limit);
+    let new_states = calculate_consequences(from, horizon, ruleset);
     tree.append(new_states);
     
     Ok(())
@@ -51,10 +55,10 @@ pub fn grow_tree_horizon_limited(
 /// Function will build all boardstates from `start`  inserting them into the `states` map.
 /// If the boardstate already exists will skip that boardstate. This function has no
 /// horizon so it won't stop generating until the stack is empty.
-pub fn calculate_all_consequences(
-    start: Board, move_limit: u8
-) -> HashMap<Board, Vec<Choice>> {
-    let (tree, stats) = breadth_first_calc_consequences(start, move_limit);
+pub fn calculate_all_consequences<H: Holding>(
+    start: Board<H>, ruleset: &Ruleset,
+) -> States<H> {
+    let (tree, stats) = breadth_first_calc_consequences(start, ruleset);
 
     stats
         .iter()
@@ -71,10 +75,10 @@ pub fn calculate_all_consequences(
     tree
 }
 
-pub fn calculate_consequences(
-    from: Board, horizon: usize, move_limit: u8,
-) -> HashMap<Board, Vec<Choice>> {
-    let (tree, stats) = bounded_breadth_first_calc_consequences(from, horizon, move_limit);
+pub fn calculate_consequences<H: Holding>(
+    from: Board<H>, horizon: usize, ruleset: &Ruleset,
+) -> States<H> {
+    let (tree, stats) = bounded_breadth_first_calc_consequences(from, horizon, ruleset);
 
     stats
         .iter()
@@ -91,11 +95,11 @@ pub fn calculate_consequences(
     tree
 }
 
-pub fn calculate_consequences_insert_limited(
-    from: Board, board_budget: usize, move_limit: u8,
-) -> HashMap<Board, Vec<Choice>> {
+pub fn calculate_consequences_insert_limited<H: Holding>(
+    from: Board<H>, board_budget: usize, ruleset: &Ruleset,
+) -> States<H> {
     let (tree, stats) = insert_budgeted_breadth_first_calc_consequences(
-        from, board_budget, move_limit,
+        from, board_budget, ruleset,
     );
 
     stats
@@ -117,11 +121,11 @@ pub fn calculate_consequences_insert_limited(
 /// branch all the way to the end and then backtracking upwards. This means that each
 /// layer will grow exponentially large but it will be easier to see how the dataset
 /// grows geometrically as the grid size/players increase linearly.
-fn breadth_first_calc_consequences(
-    start: Board, move_limit: u8,
-) -> (HashMap<Board, Vec<Choice>>, Vec<LayerStats>) {
-    let mut states: HashMap<Board, Vec<Choice>> = HashMap::new();
-    let mut current_layer: Option<Vec<Board>> = Some(vec![start]);
+fn breadth_first_calc_consequences<H: Holding>(
+    start: Board<H>, ruleset: &Ruleset,
+) -> (States<H>, Vec<LayerStats>) {
+    let mut states: States<H> = HashMap::new();
+    let mut current_layer: Option<Vec<Board<H>>> = Some(vec![start]);
     let mut layer_count: usize = 0;
     let mut layer_stats: Vec<LayerStats> = Vec::new();
     
@@ -141,11 +145,12 @@ fn breadth_first_calc_consequences(
         let mut next_layer = Vec::new();
         for board in layer {
             if !states.contains_key(&board) {
-                let choices = choices_from_board_only_pass_at_end(&board, move_limit);
+                let choices = choices_from_board(&board, ruleset);
                 next_layer.extend(
                     choices
                         .iter()
-                        .map(|choice| choice.consequence().board().to_owned())
+                        .flat_map(|choice| choice.boards())
+                        .map(|board| board.to_owned())
                 );
                 states.insert(board, choices);
 
@@ -163,11 +168,11 @@ fn breadth_first_calc_consequences(
 }
 
 /// Brute force the tree with a horizon limit. Only calculate to the depth specified.
-fn bounded_breadth_first_calc_consequences(
-    start: Board, horizon: usize, move_limit: u8,
-) -> (HashMap<Board, Vec<Choice>>, Vec<LayerStats>) {
-    let mut states: HashMap<Board, Vec<Choice>> = HashMap::new();
-    let mut current_layer: Option<Vec<Board>> = Some(vec![start]);
+fn bounded_breadth_first_calc_consequences<H: Holding>(
+    start: Board<H>, horizon: usize, ruleset: &Ruleset,
+) -> (States<H>, Vec<LayerStats>) {
+    let mut states: States<H> = HashMap::new();
+    let mut current_layer: Option<Vec<Board<H>>> = Some(vec![start]);
     let mut layer_count: usize = 0;
     let mut layer_stats: Vec<LayerStats> = Vec::new();
     
@@ -187,11 +192,12 @@ fn bounded_breadth_first_calc_consequences(
         let mut next_layer = Vec::new();
         for board in layer {
             if !states.contains_key(&board) {
-                let choices = choices_from_board_only_pass_at_end(&board, move_limit);
+                let choices = choices_from_board(&board, ruleset);
                 next_layer.extend(
                     choices
                         .iter()
-                        .map(|choice| choice.consequence().board().to_owned())
+                        .flat_map(|choice| choice.boards())
+                        .map(|board| board.to_owned())
                 );
                 states.insert(board, choices);
 
@@ -210,12 +216,12 @@ fn bounded_breadth_first_calc_consequences(
 
 /// Brute force the tree with a board insert limit. Only calculate to the boards specified.
 /// Will not cancel a partially computed depth layer.
-fn insert_budgeted_breadth_first_calc_consequences(
-    start: Board, boards: usize, move_limit: u8,
-) -> (HashMap<Board, Vec<Choice>>, Vec<LayerStats>) {
+fn insert_budgeted_breadth_first_calc_consequences<H: Holding>(
+    start: Board<H>, boards: usize, ruleset: &Ruleset,
+) -> (States<H>, Vec<LayerStats>) {
     let mut spent: usize = 0;
-    let mut states: HashMap<Board, Vec<Choice>> = HashMap::new();
-    let mut current_layer: Option<Vec<Board>> = Some(vec![start]);
+    let mut states: States<H> = HashMap::new();
+    let mut current_layer: Option<Vec<Board<H>>> = Some(vec![start]);
     let mut layer_count: usize = 0;
     let mut layer_stats: Vec<LayerStats> = Vec::new();
     
@@ -235,11 +241,12 @@ fn insert_budgeted_breadth_first_calc_consequences(
         let mut next_layer = Vec::new();
         for board in layer {
             if !states.contains_key(&board) {
-                let choices = choices_from_board_only_pass_at_end(&board, move_limit);
+                let choices = choices_from_board(&board, ruleset);
                 next_layer.extend(
                     choices
                         .iter()
-                        .map(|choice| choice.consequence().board().to_owned())
+                        .flat_map(|choice| choice.boards())
+                        .map(|board| board.to_owned())
                 );
                 states.insert(board, choices);
 
@@ -273,32 +280,45 @@ mod test {
     #[test]
     fn breadth_first_on_canned_2x1_start01() {
         let board = canned_2x1_start01();
-        let (states, _stats) = breadth_first_calc_consequences(board.clone(), 10);
-        assert!(states.len() == 3);
+        let ruleset = Ruleset::new();
+        let (states, _stats) = breadth_first_calc_consequences(board.clone(), &ruleset);
+        assert!(states.len() == 4);
         assert!(states.contains_key(&board));
     }
 
     #[test]
     fn breadth_first_on_canned_2x2_start01() {
         let board = canned_2x2_start01();
-        let (states, _stats) = breadth_first_calc_consequences(board.clone(), 10);
-        assert!(states.len() == 4);
+        let ruleset = Ruleset::new();
+        let (states, _stats) = breadth_first_calc_consequences(board.clone(), &ruleset);
+        assert!(states.len() == 9);
         assert!(states.contains_key(&board));
     }
 
     #[test]
     fn consequences_3x1_2player() {
         let board = canned_3x1_start01();
-        let consequences = calculate_all_consequences(board.clone(), 10);
+        let consequences = calculate_all_consequences(board.clone(), &Ruleset::new());
 
-        assert!(consequences.len() == 2);
+        assert!(consequences.len() == 25);
     }
 
     #[test]
     fn consequences_3x1_3player() {
         let board = canned_3x1_start05();
-        let consequences = calculate_all_consequences(board.clone(), 20);
+        let consequences = calculate_all_consequences(board.clone(), &Ruleset::new());
+
+        // Players go out as soon as their last hex falls so no boards skip their turn.
+        assert!(consequences.len() == 32);
+    }
+
+    #[test]
+    fn failed_attacks_in_tree() {
+        let board = canned_2x2_start02();
+        let tree = start_tree_horizon_limited(board.clone(), 2, &Ruleset::new());
+        let choices = tree.fetch_choices(&board).unwrap();
+        let chance = choices[0].chance().unwrap();
 
-        assert!(consequences.len() == 14);
+        assert!(tree.fetch_choices(chance.failure().board()).is_some());
     }
 }
@@ -2,35 +2,133 @@
 use std::collections::HashMap;
 use std::cell::Cell;
 use std::{fmt, ops, cmp};
+use std::hash::Hash;
 
 use derive_getters::Getters;
 
 use crate::hexagon::{Cube, Grid};
 use super::{Player, Players, player};
+use super::player::MAX_PLAYERS;
 
 pub type FromHex = Cube;
 pub type ToHex = Cube;
 pub type Capturing = u8;
+pub type Count = u8;
 pub type AttackerDice = u8;
 pub type DefenderDice = u8;
 
-pub trait Holding {
+/// The lie of the land on a hex.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    Plains,
+
+    /// The defender rolls an extra die.
+    Mountains,
+
+    /// Impassable. Can't attack or be attacked, take part in transfers or be reinforced.
+    Water,
+}
+
+impl Terrain {
+    /// Extra dice the defender of a hex rolls.
+    pub fn defence_bonus(&self) -> u8 {
+        match self {
+            Terrain::Mountains => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn passable(&self) -> bool {
+        *self != Terrain::Water
+    }
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Terrain::Plains
+    }
+}
+
+/// The bit packed contents of a hex as stored in a `Board`'s `Grid`. Each encoding limits
+/// how many players and dice a hex can have.
+pub trait Holding: Copy + Clone + PartialEq + Eq + Hash + fmt::Debug {
+    /// Highest player number that can own a hex.
+    const PLAYER_LIMIT: usize;
+
+    /// Most dice a hex can have.
+    const DICE_LIMIT: u8;
+
+    /// Panics if the `owner` or `dice` go over the limits above rather than spill into the
+    /// other bits.
     fn new(owner: Player, dice: u8, mobile: bool) -> Self;
     fn owner(&self) -> Player;
     fn dice(&self) -> u8;
     fn mobile(&self) -> bool;
 
+    /// Holdings without room for terrain are all plains.
+    fn terrain(&self) -> Terrain {
+        Terrain::Plains
+    }
+
+    /// A copy on `terrain`. Holdings without room for terrain are returned as is.
+    fn with_terrain(self, _terrain: Terrain) -> Self {
+        self
+    }
+
+    /// Whether the hex is an objective worth a victory point each turn to whoever holds
+    /// it. Never for holdings without room for it.
+    fn objective(&self) -> bool {
+        false
+    }
+
+    /// A copy with the objective marker set. Holdings without room for it are returned as
+    /// is.
+    fn with_objective(self, _objective: bool) -> Self {
+        self
+    }
+
+    /// Most dice this hex can take in place of the ruleset's `max_dice`. `None` if the hex
+    /// has no capacity of its own, which is always the case for holdings without room for
+    /// it.
+    fn capacity(&self) -> Option<u8> {
+        None
+    }
+
+    /// A copy with its own capacity. Holdings without room for it are returned as is.
+    fn with_capacity(self, _capacity: Option<u8>) -> Self {
+        self
+    }
+
+    /// A copy with the owner, dice and mobility replaced. Everything else about the hex,
+    /// such as its terrain, is kept.
+    fn update(&self, owner: Player, dice: u8, mobile: bool) -> Self {
+        Self::new(owner, dice, mobile)
+            .with_terrain(self.terrain())
+            .with_objective(self.objective())
+            .with_capacity(self.capacity())
+    }
+
+    /// Water shows as `~~~`. Objectives swap the `|` of a mobile hex for a `*` and
+    /// mountains for a `^`.
     fn as_string(&self) -> String {
-        if self.mobile() {
-            format!("{}|{}", self.owner(), self.dice())
-        } else {
-            format!("{}#{}", self.owner(), self.dice())
+        match (self.terrain(), self.mobile()) {
+            (Terrain::Water, _) => "~~~".to_owned(),
+            (_, true) if self.objective() => format!("{}*{}", self.owner(), self.dice()),
+            (Terrain::Mountains, true) => format!("{}^{}", self.owner(), self.dice()),
+            (_, true) => format!("{}|{}", self.owner(), self.dice()),
+            (_, false) => format!("{}#{}", self.owner(), self.dice()),
         }
     }
 }
 
+/// 3 bits of owner, 3 bits of dice and 1 bit of mobility. Good for up to 7 players.
 impl Holding for u8 {
+    const PLAYER_LIMIT: usize = 7;
+    const DICE_LIMIT: u8 = 7;
+
     fn new(owner: Player, dice: u8, mobile: bool) -> Self {
+        assert!(*owner.number() <= Self::PLAYER_LIMIT, "Too many players for the holding.");
+        assert!(dice <= Self::DICE_LIMIT, "Too many dice for the holding.");
         let player_num = *owner.number() as u8;
         let player_num = player_num.to_le() << 5;
         let player_num = player_num >> 5;
@@ -66,6 +164,71 @@ impl Holding for u8 {
     }
 }
 
+/// 4 bits of owner, 4 bits of dice, 1 bit of mobility, 2 bits of terrain, 1 bit for
+/// objectives and 4 bits of capacity. A capacity of zero means the hex has none of its own.
+impl Holding for u16 {
+    const PLAYER_LIMIT: usize = MAX_PLAYERS;
+    const DICE_LIMIT: u8 = 15;
+
+    fn new(owner: Player, dice: u8, mobile: bool) -> Self {
+        assert!(*owner.number() <= Self::PLAYER_LIMIT, "Too many players for the holding.");
+        assert!(dice <= Self::DICE_LIMIT, "Too many dice for the holding.");
+        let player_num = *owner.number() as u16 & 0b1111;
+        let dice = (dice as u16 & 0b1111) << 4;
+        let mobile = (mobile as u16) << 8;
+        mobile | dice | player_num
+    }
+
+    fn owner(&self) -> Player {
+        player::create((self & 0b1111) as usize)
+    }
+
+    fn dice(&self) -> u8 {
+        ((self >> 4) & 0b1111) as u8
+    }
+
+    fn mobile(&self) -> bool {
+        (self >> 8) & 1 == 1
+    }
+
+    fn terrain(&self) -> Terrain {
+        match (self >> 9) & 0b11 {
+            1 => Terrain::Mountains,
+            2 => Terrain::Water,
+            _ => Terrain::Plains,
+        }
+    }
+
+    fn with_terrain(self, terrain: Terrain) -> Self {
+        let bits: u16 = match terrain {
+            Terrain::Plains => 0,
+            Terrain::Mountains => 1,
+            Terrain::Water => 2,
+        };
+        (self & !(0b11 << 9)) | (bits << 9)
+    }
+
+    fn objective(&self) -> bool {
+        (self >> 11) & 1 == 1
+    }
+
+    fn with_objective(self, objective: bool) -> Self {
+        (self & !(1 << 11)) | ((objective as u16) << 11)
+    }
+
+    fn capacity(&self) -> Option<u8> {
+        match (self >> 12) & 0b1111 {
+            0 => None,
+            capacity => Some(capacity as u8),
+        }
+    }
+
+    fn with_capacity(self, capacity: Option<u8>) -> Self {
+        let bits = capacity.map(|c| c.min(Self::DICE_LIMIT) as u16).unwrap_or(0);
+        (self & !(0b1111 << 12)) | (bits << 12)
+    }
+}
+
 /// A territorial hold on a particular tile.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct Hold {
@@ -77,11 +240,43 @@ pub struct Hold {
 
     /// Barred from moving if false
     mobile: bool,
+
+    terrain: Terrain,
+    objective: bool,
+    capacity: Option<u8>,
+
+    /// Whether this is the owner's capital. Kept by the `Board`, only here for display.
+    capital: bool,
+}
+
+impl Hold {
+    /// Decode any other `Holding` into a `Hold`.
+    pub fn from_holding<H: Holding>(holding: &H) -> Self {
+        Hold::new(holding.owner(), holding.dice(), holding.mobile())
+            .with_terrain(holding.terrain())
+            .with_objective(holding.objective())
+            .with_capacity(holding.capacity())
+    }
+
+    pub fn with_capital(self, capital: bool) -> Self {
+        Hold { capital, ..self }
+    }
 }
 
 impl Holding for Hold {
+    const PLAYER_LIMIT: usize = MAX_PLAYERS;
+    const DICE_LIMIT: u8 = u8::max_value();
+
     fn new(owner: Player, dice: u8, mobile: bool) -> Self {
-        Hold { owner, dice, mobile }
+        Hold {
+            owner,
+            dice,
+            mobile,
+            terrain: Terrain::default(),
+            objective: false,
+            capacity: None,
+            capital: false,
+        }
     }
 
     fn owner(&self) -> Player {
@@ -95,11 +290,40 @@ impl Holding for Hold {
     fn mobile(&self) -> bool {
         self.mobile
     }
+
+    fn terrain(&self) -> Terrain {
+        self.terrain
+    }
+
+    fn with_terrain(self, terrain: Terrain) -> Self {
+        Hold { terrain, ..self }
+    }
+
+    fn objective(&self) -> bool {
+        self.objective
+    }
+
+    fn with_objective(self, objective: bool) -> Self {
+        Hold { objective, ..self }
+    }
+
+    fn capacity(&self) -> Option<u8> {
+        self.capacity
+    }
+
+    fn with_capacity(self, capacity: Option<u8>) -> Self {
+        Hold { capacity, ..self }
+    }
 }
 
+/// Capitals show with an `@` between the owner and dice.
 impl fmt::Display for Hold {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.as_string())
+        if self.capital {
+            write!(f, "{}@{}", self.owner, self.dice)
+        } else {
+            write!(f, "{}", self.as_string())
+        }
     }
 }
 
@@ -109,26 +333,273 @@ impl Default for Hold {
     }
 }
 
-/// The full state of the game. Represents an iteration of play.
+/// The full state of the game. Represents an iteration of play. The hexes are stored as
+/// `u8`s unless another `Holding` is needed for more players or dice. The players must
+/// fit within the `Holding::PLAYER_LIMIT`.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Getters)]
-pub struct Board {
+pub struct Board<H: Holding = u8> {
     players: Players,
     //grid: Grid<Hold>,
-    grid: Grid<u8>,
+    grid: Grid<H>,
     captured_dice: u8,
     moved: u8,
+
+    /// Dice the current player has left to place during a manual placement phase.
+    reinforcing: u8,
+
+    /// Reinforcements each player has banked because their hexes were full. Indexed by
+    /// player number less one.
+    reserves: [u8; MAX_PLAYERS],
+
+    /// How many times the current player has moved dice between their own hexes.
+    transferred: u8,
+
+    /// The capital hex of each player that has one. Indexed by player number less one.
+    capitals: [Option<Cube>; MAX_PLAYERS],
+
+    /// Victory points each player has earned from holding objectives. Indexed by player
+    /// number less one.
+    tally: [u8; MAX_PLAYERS],
 }
 
-impl Board {
-    pub fn new(players: Players, grid: Grid<u8>, captured_dice: u8, moved: u8) -> Self {
-        Board { players, grid, captured_dice, moved }
+impl<H: Holding> Board<H> {
+    /// Panics if there are more `players` than the `Holding` can store.
+    pub fn new(players: Players, grid: Grid<H>, captured_dice: u8, moved: u8) -> Self {
+        assert!(players.total() <= H::PLAYER_LIMIT, "Too many players for the holding.");
+        Board {
+            players,
+            grid,
+            captured_dice,
+            moved,
+            reinforcing: 0,
+            reserves: [0; MAX_PLAYERS],
+            transferred: 0,
+            capitals: [None; MAX_PLAYERS],
+            tally: [0; MAX_PLAYERS],
+        }
+    }
+
+    /// The board that follows this one with `players`, `grid` and the move counts swapped
+    /// in. Reserves, capitals and victory points carry over while the rest starts afresh.
+    pub fn next(
+        &self, players: Players, grid: Grid<H>, captured_dice: u8, moved: u8,
+    ) -> Self {
+        Board::new(players, grid, captured_dice, moved)
+            .with_reserves(self.reserves)
+            .with_capitals(self.capitals)
+            .with_tally(self.tally)
+    }
+
+    /// Put the board into a placement phase with `reinforcing` dice left to place.
+    pub fn with_reinforcing(self, reinforcing: u8) -> Self {
+        Board { reinforcing, ..self }
+    }
+
+    /// Swap in `players`. Handy for setting up teams on an existing board.
+    pub fn with_players(self, players: Players) -> Self {
+        Board { players, ..self }
+    }
+
+    /// Set how many transfers the current player has made this turn.
+    pub fn with_transferred(self, transferred: u8) -> Self {
+        Board { transferred, ..self }
+    }
+
+    /// Carry over the banked reinforcements of all players.
+    pub fn with_reserves(self, reserves: [u8; MAX_PLAYERS]) -> Self {
+        Board { reserves, ..self }
+    }
+
+    /// Set the banked reinforcements of a single `player`.
+    pub fn with_reserve(mut self, player: Player, reserve: u8) -> Self {
+        if let Some(slot) = self.reserves.get_mut(player.number().wrapping_sub(1)) {
+            *slot = reserve;
+        }
+        self
+    }
+
+    /// Change the terrain of the hex at `hex`. Has no effect on holdings without room for
+    /// terrain.
+    pub fn with_terrain(self, hex: Cube, terrain: Terrain) -> Self {
+        let grid = self.grid.fork_with(|cube, hold| {
+            if *cube == hex {
+                hold.with_terrain(terrain)
+            } else {
+                hold
+            }
+        });
+        Board { grid, ..self }
+    }
+
+    /// Mark or unmark the hex at `hex` as an objective. Has no effect on holdings without
+    /// room for objectives.
+    pub fn with_objective(self, hex: Cube, objective: bool) -> Self {
+        let grid = self.grid.fork_with(|cube, hold| {
+            if *cube == hex {
+                hold.with_objective(objective)
+            } else {
+                hold
+            }
+        });
+        Board { grid, ..self }
+    }
+
+    /// Give the hex at `hex` a `capacity` of its own or clear it with `None`. Any dice over
+    /// the new capacity are removed. Has no effect on holdings without room for capacity.
+    pub fn with_capacity(self, hex: Cube, capacity: Option<u8>) -> Self {
+        let grid = self.grid.fork_with(|cube, hold| {
+            if *cube == hex {
+                let hold = hold.with_capacity(capacity);
+                let dice = hold.capacity().map_or(hold.dice(), |c| hold.dice().min(c));
+                hold.update(hold.owner(), dice, hold.mobile())
+            } else {
+                hold
+            }
+        });
+        Board { grid, ..self }
+    }
+
+    /// Carry over the victory points of all players.
+    pub fn with_tally(self, tally: [u8; MAX_PLAYERS]) -> Self {
+        Board { tally, ..self }
+    }
+
+    /// Set the victory points of a single `player`.
+    pub fn with_points(mut self, player: Player, points: u8) -> Self {
+        if let Some(slot) = self.tally.get_mut(player.number().wrapping_sub(1)) {
+            *slot = points;
+        }
+        self
+    }
+
+    /// Victory points earned by `player`.
+    pub fn points(&self, player: Player) -> u8 {
+        self.tally
+            .get(player.number().wrapping_sub(1))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Carry over the capitals of all players.
+    pub fn with_capitals(self, capitals: [Option<Cube>; MAX_PLAYERS]) -> Self {
+        Board { capitals, ..self }
+    }
+
+    /// Make `capital` the capital hex of `player` or take their capital away with `None`.
+    /// A player whose capital is captured loses all their hexes.
+    pub fn with_capital(mut self, player: Player, capital: Option<Cube>) -> Self {
+        if let Some(slot) = self.capitals.get_mut(player.number().wrapping_sub(1)) {
+            *slot = capital;
+        }
+        self
+    }
+
+    pub fn capital(&self, player: Player) -> Option<Cube> {
+        self.capitals
+            .get(player.number().wrapping_sub(1))
+            .cloned()
+            .unwrap_or(None)
+    }
+
+    /// Whether `hex` is the capital of the player holding it.
+    pub fn is_capital(&self, hex: &Cube) -> bool {
+        self.grid
+            .fetch(hex)
+            .map(|hold| self.capital(hold.owner()) == Some(*hex))
+            .unwrap_or(false)
+    }
+
+    /// Reinforcements banked by `player`.
+    pub fn reserve(&self, player: Player) -> u8 {
+        self.reserves
+            .get(player.number().wrapping_sub(1))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// The board as `player` sees it under fog of war. Hexes of the player and their team
+    /// are visible along with every hex next to them. Everything else is hidden.
+    pub fn view_for(&self, player: Player) -> BoardView {
+        let players = self.players;
+        let grid = &self.grid;
+        let seen = |cube: &Cube| {
+            grid.fetch(cube)
+                .map(|hold| {
+                    hold.terrain().passable() && players.allies(hold.owner(), player)
+                })
+                .unwrap_or(false)
+        };
+
+        let view: Grid<Sighting> = grid
+            .iter()
+            .map(|ht| {
+                let coordinate = *ht.coordinate();
+                let hold = *ht.data();
+                let visible = seen(&coordinate)
+                    || coordinate.neighbours().iter().any(|neighbour| seen(neighbour));
+                let sighting = if visible {
+                    let capital = self.is_capital(&coordinate);
+                    Sighting::Visible(Hold::from_holding(&hold).with_capital(capital))
+                } else {
+                    Sighting::Hidden
+                };
+                (coordinate, sighting)
+            })
+            .collect();
+
+        BoardView {
+            viewer: player,
+            current: players.current(),
+            grid: view.change_shape(grid.shape()),
+        }
     }
 }
 
-impl fmt::Display for Board {
+/// What a player can make out of a hex under fog of war.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Sighting {
+    Visible(Hold),
+    Hidden,
+}
+
+impl fmt::Display for Sighting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sighting::Visible(hold) => write!(f, "{}", hold),
+            Sighting::Hidden => write!(f, "???"),
+        }
+    }
+}
+
+/// A `Board` redacted for one player with `Board::view_for`. Safe to send to that player
+/// as it holds nothing they aren't allowed to see.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct BoardView {
+    /// Who the view is for.
+    viewer: Player,
+
+    /// Whose turn it is.
+    current: Player,
+
+    grid: Grid<Sighting>,
+}
+
+impl fmt::Display for BoardView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Viewing as: {}, Current Player: {}\nBoard =============\n{}",
+            &self.viewer,
+            &self.current,
+            &self.grid,
+        )
+    }
+}
+
+impl<H: Holding> fmt::Display for Board<H> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Little hack. Since we've switched to using a bit packed u8 instead of `Hold`,
-        // it screws up the display. So we'll generate a once-off `Grid<Hold>` just
+        // Little hack. Since we've switched to using a bit packed `Holding` instead of
+        // `Hold`, it screws up the display. So we'll generate a once-off `Grid<Hold>` just
         // to print it to screen.
         let (columns, rows) = match self.grid.shape() {
             crate::hexagon::grid::Shape::Rectangular { columns, rows } => (columns, rows),
@@ -137,20 +608,38 @@ impl fmt::Display for Board {
         
         let display_grid: Grid<Hold> = self.grid
             .iter()
-            .map(|ht| (
-                *ht.coordinate(),
-                Hold::new(ht.data().owner(), ht.data().dice(), ht.data().mobile())
-            ))
+            .map(|ht| {
+                let hold = Hold::from_holding(ht.data())
+                    .with_capital(self.is_capital(ht.coordinate()));
+                (*ht.coordinate(), hold)
+            })
             .collect();
         let display_grid = display_grid.change_to_rectangle(columns, rows);
         
+        let reserves = self.players
+            .playing()
+            .into_iter()
+            .map(|player| format!("{}: {}", player, self.reserve(player)))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let points = self.players
+            .playing()
+            .into_iter()
+            .map(|player| format!("{}: {}", player, self.points(player)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        
         write!(
             f,
             "Current Player: {}\nCaptured Dice: {}, Moved: {} time(s). \
-             \nBoard =============\n{}",
+             Reinforcing: {}\nReserves: {}\nPoints: {}\nBoard =============\n{}",
             &self.players.current(),
             &self.captured_dice,
             &self.moved,
+            &self.reinforcing,
+            &reserves,
+            &points,
             &display_grid,
         )
     }
@@ -161,6 +650,16 @@ impl fmt::Display for Board {
 pub enum Action {
     Attack(FromHex, ToHex, AttackerDice, DefenderDice),
     Pass,
+
+    /// Place one reinforcement die during a manual placement phase.
+    Reinforce(Cube),
+
+    /// Move dice from one of the player's hexes to a neighbouring hex they also own.
+    Transfer(FromHex, ToHex, Count),
+
+    /// The player leaves the game. They needn't be the current player. Never offered as a
+    /// `Choice`, see `Session::resign`.
+    Resign(Player),
 }
 
 impl Action {
@@ -180,28 +679,37 @@ impl fmt::Display for Action {
                 write!(f, "Attack from {} into {} capturing {} dice.", from, to, capturing)
             },
             Action::Pass => write!(f, "Pass turn."),
+            Action::Reinforce(hex) => write!(f, "Reinforce {} with one die.", hex),
+            Action::Resign(player) => write!(f, "Player {} resigns.", player),
+            Action::Transfer(from, to, count) => {
+                write!(f, "Transfer {} dice from {} into {}.", count, from, to)
+            },
         }
     }
 }
 
 /// What follows from a `Move`.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Consequence {
-    Stalemate(Board),
-    Continue(Board),
-    TurnOver(Board),
-    GameOver(Board),
-    Winner(Board),
+pub enum Consequence<H: Holding = u8> {
+    Stalemate(Board<H>),
+    Continue(Board<H>),
+    TurnOver(Board<H>),
+    GameOver(Board<H>),
+    Winner(Board<H>),
+
+    /// A player has earned enough victory points to win. They may not be the current one.
+    VictoryPoints(Board<H>),
 }
 
-impl Consequence {
-    pub fn board(&self) -> &Board {
+impl<H: Holding> Consequence<H> {
+    pub fn board(&self) -> &Board<H> {
         match self {
             Consequence::Stalemate(ref b) => b,
             Consequence::Continue(ref b) => b,
             Consequence::TurnOver(ref b) => b,
             Consequence::GameOver(ref b) => b,
-            Consequence::Winner(ref b) => b
+            Consequence::Winner(ref b) => b,
+            Consequence::VictoryPoints(ref b) => b,
         }
     }
 }
@@ -225,6 +733,15 @@ impl Score {
     pub fn increment_distance(&self) -> Self {
         Score::new(self.destination, self.distance + 1)
     }
+
+    /// The expected score from a roll of the dice that leads to `success` with the
+    /// probability `odds` and to `failure` otherwise.
+    pub fn expected(success: Score, failure: Score, odds: f64) -> Self {
+        let destination = success.destination * odds + failure.destination * (1_f64 - odds);
+        let distance = success.distance as f64 * odds
+            + failure.distance as f64 * (1_f64 - odds);
+        Score::new(destination, distance.round() as usize)
+    }
 }
 
 /// Custom impl since if the destination scores are equal, a smaller distance is better.
@@ -258,29 +775,70 @@ impl Default for Score {
     }
 }
 
+/// The dice roll behind an attacking `Choice`. The `Choice`'s own `Consequence` is what
+/// happens on `success`, otherwise it's the `failure`.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct Chance<H: Holding = u8> {
+    /// Probability from 0 to 1 of the attack succeeding.
+    success: f64,
+    failure: Consequence<H>,
+}
+
+impl<H: Holding> Chance<H> {
+    pub fn new(success: f64, failure: Consequence<H>) -> Self {
+        Chance { success, failure }
+    }
+}
+
 /// A `Choice` which that is an `Action` with its `Consequence`.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Choice {
+pub struct Choice<H: Holding = u8> {
     action: Action,
-    consequence: Consequence,
+    consequence: Consequence<H>,
+
+    /// Present when the `Consequence` depends on a roll of the dice.
+    chance: Option<Chance<H>>,
 
     /// Filled in AI phase when scoring each move. 
     score: Cell<Option<Score>>,    
 }
 
-impl Choice {
-    pub fn new(action: Action, consequence: Consequence) -> Self {
-        Choice { action, consequence, score: Cell::new(None) }
+impl<H: Holding> Choice<H> {
+    pub fn new(action: Action, consequence: Consequence<H>) -> Self {
+        Choice { action, consequence, chance: None, score: Cell::new(None) }
+    }
+
+    /// Make this a chance node which only leads to its `Consequence` on success.
+    pub fn with_chance(self, chance: Chance<H>) -> Self {
+        Choice { chance: Some(chance), ..self }
     }
 
     pub fn action(&self) -> &Action {
         &self.action
     }
 
-    pub fn consequence(&self) -> &Consequence {
+    pub fn consequence(&self) -> &Consequence<H> {
         &self.consequence
     }
 
+    pub fn chance(&self) -> Option<&Chance<H>> {
+        self.chance.as_ref()
+    }
+
+    /// Probability from 0 to 1 of the `Consequence` coming about. Only attacks have odds.
+    pub fn odds(&self) -> Option<f64> {
+        self.chance.as_ref().map(|chance| chance.success)
+    }
+
+    /// Every board this choice can lead to. The success board is always first.
+    pub fn boards(&self) -> Vec<&Board<H>> {
+        let mut boards = vec![self.consequence.board()];
+        if let Some(chance) = self.chance.as_ref() {
+            boards.push(chance.failure.board());
+        }
+        boards
+    }
+
     pub fn score(&self) -> Option<Score> {
         self.score.get()
     }
@@ -296,17 +854,19 @@ impl Choice {
 
 /// The game tree. Contains all moves possible from the starting state.
 #[derive(Debug, Clone, Getters)]
-pub struct Tree {
-    root: Board,
-    states: HashMap<Board, Vec<Choice>>,
+pub struct Tree<H: Holding = u8> {
+    root: Board<H>,
+    states: HashMap<Board<H>, Vec<Choice<H>>>,
 }
 
-impl Tree {
-    pub (in crate::game) fn new(root: Board, states: HashMap<Board, Vec<Choice>>) -> Self {
+impl<H: Holding> Tree<H> {
+    pub (in crate::game) fn new(
+        root: Board<H>, states: HashMap<Board<H>, Vec<Choice<H>>>,
+    ) -> Self {
         Tree { root, states }
     }
 
-    pub (in crate::game) fn empty(root: Board) -> Self {
+    pub (in crate::game) fn empty(root: Board<H>) -> Self {
         Tree {
             root,
             states: HashMap::new(),
@@ -319,7 +879,7 @@ impl Tree {
     }
      */
 
-    pub (in crate::game) fn append(&mut self, extra: HashMap<Board, Vec<Choice>>) {
+    pub (in crate::game) fn append(&mut self, extra: HashMap<Board<H>, Vec<Choice<H>>>) {
         extra
             .into_iter()
             .for_each(|(board, choices)| if !self.states.contains_key(&board) {
@@ -328,7 +888,7 @@ impl Tree {
     }
     
     /// Convenience method to save on calling the getters.
-    pub fn fetch_choices(&self, board: &Board) -> Option<&[Choice]> {
+    pub fn fetch_choices(&self, board: &Board<H>) -> Option<&[Choice<H>]> {
         self.states.get(board).map(|v| v.as_slice())
     }
 }
@@ -428,15 +988,18 @@ impl fmt::Display for Totals {
 #[cfg(test)]
 mod test {
     use std::error;
+    use std::num::NonZeroU8;
 
     use crate::game;
-    use super::super::{build_tree, Player};
+    use super::super::{build_tree, Player, Players, Ruleset};
     use super::*;
 
     #[test]
     fn board_matches_board_2x1() -> Result<(), Box<dyn error::Error>> {
         let start = game::canned_2x1_start01();
-        let tree = build_tree(start.clone(), 1);
+        let tree = build_tree(
+            start.clone(), Ruleset::new().set_move_limit(NonZeroU8::new(1).unwrap()),
+        );
 
         assert!(tree.root == start);
 
@@ -446,13 +1009,89 @@ mod test {
     #[test]
     fn board_matches_board_2x2() -> Result<(), Box<dyn error::Error>> {
         let start = game::canned_2x2_start01();
-        let tree = build_tree(start.clone(), 1);
+        let tree = build_tree(
+            start.clone(), Ruleset::new().set_move_limit(NonZeroU8::new(1).unwrap()),
+        );
 
         assert!(tree.root == start);
 
         Ok(())
     }
 
+    #[test]
+    fn fog_of_war() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes = vec![
+            (Cube::from((0, 0)), u8::new(player1, 2, true)),
+            (Cube::from((1, 0)), u8::new(player2, 3, true)),
+            (Cube::from((2, 0)), u8::new(player2, 4, true)),
+            (Cube::from((3, 0)), u8::new(player2, 5, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(4, 1), 0, 0);
+
+        let view = board.view_for(player1);
+        let seen = |x: i32| *view.grid().fetch(Cube::from((x, 0))).unwrap();
+
+        assert!(seen(0) == Sighting::Visible(Hold::new(player1, 2, true)));
+        assert!(seen(1) == Sighting::Visible(Hold::new(player2, 3, true)));
+        assert!(seen(2) == Sighting::Hidden);
+        assert!(seen(3) == Sighting::Hidden);
+        assert!(*view.current() == player1);
+
+        let view = board.view_for(player2);
+        assert!(view.grid().iter().all(|ht| *ht.data() != Sighting::Hidden));
+
+        Ok(())
+    }
+
+    #[test]
+    fn no_sight_from_water() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes = vec![
+            (Cube::from((0, 0)), u16::new(player1, 2, true)),
+            (Cube::from((1, 0)), u16::new(player1, 0, true).with_terrain(Terrain::Water)),
+            (Cube::from((2, 0)), u16::new(player2, 4, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(3, 1), 0, 0);
+
+        let view = board.view_for(player1);
+        assert!(*view.grid().fetch(Cube::from((1, 0))).unwrap() != Sighting::Hidden);
+        assert!(*view.grid().fetch(Cube::from((2, 0))).unwrap() == Sighting::Hidden);
+    }
+
+    #[test]
+    fn next_board_carries_over() {
+        let player1 = Player::new(1, 'A');
+        let board = game::canned_2x1_start01()
+            .with_reserve(player1, 3)
+            .with_capital(player1, Some((0, 0).into()))
+            .with_points(player1, 2)
+            .with_transferred(1);
+        let next = board.next(board.players().next(), board.grid().to_owned(), 0, 0);
+
+        assert!(next.reserve(player1) == 3);
+        assert!(next.capital(player1) == Some((0, 0).into()));
+        assert!(next.points(player1) == 2);
+        assert!(*next.transferred() == 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_players_for_holding() {
+        let grid = game::canned_1x1_start().grid().to_owned();
+        Board::new(Players::new(8), grid, 0, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_dice_for_holding() {
+        u8::new(Player::new(1, 'A'), 8, true);
+    }
+
     #[test]
     fn u8_holding_01() -> Result<(), Box<dyn error::Error>> {
         let player1 = Player::new(1, 'A');
@@ -489,4 +1128,56 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn neutral_holding() -> Result<(), Box<dyn error::Error>> {
+        let holding = u8::new(Player::neutral(), 4, true);
+
+        assert!(holding.owner().is_neutral());
+        assert!(holding.dice() == 4);
+        assert!(u16::new(Player::default(), 1, true).owner() == Player::neutral());
+
+        Ok(())
+    }
+
+    #[test]
+    fn u16_holding() -> Result<(), Box<dyn error::Error>> {
+        let player8 = Player::new(8, 'H');
+        let holding = u16::new(player8, 15, false);
+
+        assert!(holding.owner() == player8);
+        assert!(holding.dice() == 15);
+        assert!(holding.mobile() == false);
+
+        let player1 = Player::new(1, 'A');
+        let holding = u16::new(player1, 8, true);
+
+        assert!(holding.owner() == player1);
+        assert!(holding.dice() == 8);
+        assert!(holding.mobile() == true);
+
+        let holding = holding.with_terrain(Terrain::Water);
+        assert!(holding.terrain() == Terrain::Water);
+        assert!(holding.update(player8, 2, false).terrain() == Terrain::Water);
+        assert!(holding.dice() == 8);
+
+        let holding = holding.with_objective(true);
+        assert!(holding.objective() && holding.terrain() == Terrain::Water);
+        assert!(holding.update(player8, 2, false).objective());
+        assert!(!holding.with_objective(false).objective());
+
+        let holding = holding.with_capacity(Some(2));
+        assert!(holding.capacity() == Some(2) && holding.objective());
+        assert!(holding.update(player1, 1, true).capacity() == Some(2));
+        assert!(holding.with_capacity(None).capacity().is_none());
+        assert!(holding.with_capacity(Some(20)).capacity() == Some(15));
+
+        // No room for terrain, objectives or capacity in a `u8`.
+        let holding = u8::new(player1, 2, true).with_terrain(Terrain::Water);
+        assert!(holding.terrain() == Terrain::Plains);
+        assert!(!holding.with_objective(true).objective());
+        assert!(holding.with_capacity(Some(2)).capacity().is_none());
+
+        Ok(())
+    }
 }
@@ -4,13 +4,14 @@
 //! This exists in its own module as it contains lots of code and doesn't depend on
 //! anything else within this project.
 use std::{fmt, mem};
+use std::hash::{Hash, Hasher};
 use std::num::NonZeroUsize;
 
 use rand::Rng;
 use rand::distributions::Distribution;
 use derive_getters::Getters;
 
-const MAX_PLAYERS: usize = 6;
+pub (in crate::game) const MAX_PLAYERS: usize = 8;
 
 /// Describes a player.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Getters)]
@@ -26,15 +27,26 @@ impl Player {
             display,
         }
     }
-}
 
-impl Default for Player {
-    fn default() -> Self {
+    /// Owner of the hexes that belong to nobody. Neutral hexes never attack or get
+    /// reinforced but can be conquered. Numbered 0 so it's never one of the `Players`.
+    pub fn neutral() -> Self {
         Player {
-            number: MAX_PLAYERS + 1,
+            number: 0,
             display: '~',
         }
     }
+
+    pub fn is_neutral(&self) -> bool {
+        self.number == 0
+    }
+}
+
+/// Nobody. See `Player::neutral`.
+impl Default for Player {
+    fn default() -> Self {
+        Player::neutral()
+    }
 }
 
 impl fmt::Display for Player {
@@ -43,10 +55,13 @@ impl fmt::Display for Player {
     }
 }
 
-/// Relies on `MAX_PLAYERS` constant to check the num.
+/// Relies on `MAX_PLAYERS` constant to check the num. Zero is the neutral player.
 pub (in crate::game) fn create(num: usize) -> Player {
-    assert!(num > 0);
     assert!(num <= MAX_PLAYERS);
+
+    if num == 0 {
+        return Player::neutral();
+    }
     
     let character: char = ((64 + num) as u8).into();
     Player::new(num, character)
@@ -54,13 +69,24 @@ pub (in crate::game) fn create(num: usize) -> Player {
 
 /// Player management rolled into one struct. Keeps track of the current player and
 /// emits the next player. There is an upper limit of `MAX_PLAYERS` players.
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+///
+/// The turn numbers are bookkeeping and are left out when comparing or hashing. The same
+/// position reached on a different turn is the same board in the game tree.
+#[derive(Debug, Copy, Clone, Eq)]
 pub struct Players {
     players: usize,
     current: usize,
     count: usize,
     playing: [Option<Player>; MAX_PLAYERS],
-    out: [Option<Player>; MAX_PLAYERS],
+
+    /// The turn being played counting from 1. Each player's go is one turn.
+    turn: usize,
+
+    /// Players that are out of the game with the turn they went out on, first out first.
+    out: [Option<(Player, usize)>; MAX_PLAYERS],
+
+    /// Team of each player indexed by player number less one. `None` plays on their own.
+    teams: [Option<usize>; MAX_PLAYERS],
 }
 
 impl Players {
@@ -69,13 +95,7 @@ impl Players {
     pub fn new(players: usize) -> Self {
         let current = 0;
         let mut playing = [None; MAX_PLAYERS];
-        let players = if players > MAX_PLAYERS {
-            MAX_PLAYERS
-        } else if players < 2 {
-            2
-        } else {
-            players
-        };
+        let players = players.clamp(2, MAX_PLAYERS);
 
         playing
             .iter_mut()
@@ -92,7 +112,9 @@ impl Players {
             current,
             count: players,
             playing,            
+            turn: 1,
             out: [None; MAX_PLAYERS],
+            teams: [None; MAX_PLAYERS],
         }
     }
 
@@ -100,13 +122,24 @@ impl Players {
         self.count
     }
 
+    /// Number of players the game started with, including those already out.
+    pub fn total(&self) -> usize {
+        self.players
+    }
+
     pub fn current(&self) -> Player {
         self.playing[self.current].unwrap()
     }
 
+    /// The turn being played counting from 1.
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
     /// Create a copy of self with the current player index incremented.
     pub fn next(&self) -> Self {
         let mut new_self = self.to_owned();
+        new_self.turn += 1;
         new_self.current += 1;
         if new_self.current >= self.count {
             new_self.current = 0;
@@ -119,25 +152,53 @@ impl Players {
     /// next one. It is not possible to remove the last player as subsequent calls will just
     /// return a copy of `self`.
     pub fn remove_current(&self) -> Self {
+        self.remove(self.current())
+    }
+
+    /// Like `remove_current` but for any `player` still playing. If it isn't the current
+    /// player's turn that's removed, it stays their turn. Removing a player who is already
+    /// out returns a copy of `self`. The player goes out on the turn being played.
+    pub fn remove(&self, player: Player) -> Self {
         let mut new_self = self.to_owned();
-        
+
+        let index = match self.playing[..self.count]
+            .iter()
+            .position(|slot| *slot == Some(player))
+        {
+            Some(index) => index,
+            None => return new_self,
+        };
+
         if new_self.count == 1 {
             return new_self;
         }
 
         new_self.count -= 1;
-        let mut player = new_self.playing[new_self.current].take();
+        let player = new_self.playing[index].take();
         assert!(player.is_some());
-        mem::swap(&mut new_self.out[new_self.current], &mut player);
+        let last = new_self.out
+            .iter()
+            .position(|slot| slot.is_none())
+            .expect("There's always a slot for a player still playing.");
+        new_self.out[last] = player.map(|player| (player, self.turn));
 
-        // shuffle down by one all after current.
-        for i in (new_self.current + 1)..MAX_PLAYERS {
+        // shuffle down by one all after the removed player.
+        for i in (index + 1)..MAX_PLAYERS {
             if new_self.playing[i].is_some() {
                 let mut shuffle = new_self.playing[i].take();
                 mem::swap(&mut new_self.playing[i - 1], &mut shuffle);
             }
         }
 
+        // Taking out the current player ends their turn.
+        if index == new_self.current {
+            new_self.turn += 1;
+        }
+
+        if index < new_self.current {
+            new_self.current -= 1;
+        }
+
         if new_self.current >= new_self.count {
             new_self.current = 0;
         }
@@ -145,6 +206,64 @@ impl Players {
         new_self
     }
 
+    /// Create a copy of self with `player` moved into `team`. Every player starts out on
+    /// their own.
+    pub fn with_team(&self, player: Player, team: usize) -> Self {
+        let mut new_self = self.to_owned();
+        if let Some(slot) = new_self.teams.get_mut(player.number.wrapping_sub(1)) {
+            *slot = Some(team);
+        }
+        new_self
+    }
+
+    /// The team `player` is on. `None` if they play on their own.
+    pub fn team(&self, player: Player) -> Option<usize> {
+        self.teams
+            .get(player.number.wrapping_sub(1))
+            .and_then(|team| *team)
+    }
+
+    /// Whether both players are on the same team. A player is always their own ally. The
+    /// neutral player is nobody else's ally.
+    pub fn allies(&self, player: Player, other: Player) -> bool {
+        if player.is_neutral() || other.is_neutral() {
+            return player == other;
+        }
+        match (self.team(player), self.team(other)) {
+            (Some(team), Some(other_team)) => team == other_team,
+            _ => player == other,
+        }
+    }
+
+    /// Every player on the same team as `player` including those knocked out.
+    pub fn team_members(&self, player: Player) -> Vec<Player> {
+        (1..=self.players)
+            .map(create)
+            .filter(|member| self.allies(player, *member))
+            .collect()
+    }
+
+    /// All the players that are out of the game, first out first.
+    pub fn eliminated(&self) -> Vec<Player> {
+        self.out
+            .iter()
+            .filter_map(|slot| slot.map(|(player, _)| player))
+            .collect()
+    }
+
+    /// Like `eliminated` along with the turn each player went out on.
+    pub fn eliminations(&self) -> Vec<(Player, usize)> {
+        self.out
+            .iter()
+            .filter_map(|slot| *slot)
+            .collect()
+    }
+
+    /// The `out` slots without the turns.
+    fn out_order(&self) -> impl Iterator<Item = Option<Player>> + '_ {
+        self.out.iter().map(|slot| slot.map(|(player, _)| player))
+    }
+
     /// Return a copy of all players that are still playing.
     pub fn playing(&self) -> Vec<Player> {
         let mut players: Vec<Player> = Vec::new();
@@ -158,6 +277,28 @@ impl Players {
     }
 }
 
+impl PartialEq for Players {
+    fn eq(&self, other: &Self) -> bool {
+        self.players == other.players
+            && self.current == other.current
+            && self.count == other.count
+            && self.playing == other.playing
+            && self.out_order().eq(other.out_order())
+            && self.teams == other.teams
+    }
+}
+
+impl Hash for Players {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.players.hash(state);
+        self.current.hash(state);
+        self.count.hash(state);
+        self.playing.hash(state);
+        self.out_order().for_each(|player| player.hash(state));
+        self.teams.hash(state);
+    }
+}
+
 impl Distribution<Player> for Players {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Player {
         self.playing[rng.gen_range(0, self.count)].unwrap()
@@ -216,4 +357,72 @@ mod test {
         let players = players.next();
         assert!(player2 == players.current());
     }
+
+    #[test]
+    fn remove_any_player() {
+        let players = Players::new(4);
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let player4 = Player::new(4, 'D');
+
+        // Removing someone else keeps the turn where it is.
+        let players = players.next().next();
+        assert!(player3 == players.current());
+        let players = players.remove(player1);
+        assert!(players.player_count() == 3);
+        assert!(player3 == players.current());
+        assert!(players.playing() == vec![player2, player3, player4]);
+
+        // Removing the current player passes the turn on.
+        let players = players.remove(player3);
+        assert!(player4 == players.current());
+        let players = players.remove(player4);
+        assert!(player2 == players.current());
+
+        // Nobody to remove, or nobody left to play.
+        assert!(players.remove(player1) == players);
+        assert!(players.remove(player2) == players);
+        assert!(players.eliminated() == vec![player1, player3, player4]);
+    }
+
+    #[test]
+    fn elimination_turns() {
+        let players = Players::new(3);
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+
+        // 'C' is knocked out during the turn of 'A' and 'A' resigns on theirs.
+        let players = players.remove(player3);
+        assert!(players.turn() == 1);
+        let players = players.next().next().remove_current();
+        assert!(players.turn() == 4);
+        assert!(players.current() == player2);
+        assert!(players.eliminations() == vec![(player3, 1), (player1, 3)]);
+
+        // Turns don't tell boards apart.
+        assert!(players == players.next().next());
+    }
+
+    #[test]
+    fn teams() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let players = Players::new(3);
+
+        assert!(!players.allies(player1, player3));
+        assert!(players.team_members(player1) == vec![player1]);
+
+        // Sharing a number with a team doesn't put a player on it.
+        let players = players.with_team(player3, 1);
+        assert!(!players.allies(player1, player3));
+
+        let players = players.with_team(player1, 1).remove_current();
+        assert!(players.allies(player1, player3));
+        assert!(!players.allies(player2, player3));
+        assert!(!players.with_team(player2, 0).allies(player2, Player::neutral()));
+        assert!(players.team_members(player3) == vec![player1, player3]);
+    }
 }
@@ -0,0 +1,156 @@
+//! Exact odds of dice battles. The sum distributions and the odds of every pairing of dice
+//! a holding can have are worked out once per thread for each kind of `Die` and then
+//! looked up.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::ruleset::{Die, TieRule, HOLDING_DICE_LIMIT};
+
+const SIZE: usize = HOLDING_DICE_LIMIT as usize + 1;
+
+thread_local! {
+    static TABLES: RefCell<HashMap<Die, Rc<Table>>> = RefCell::new(HashMap::new());
+}
+
+/// Precomputed distributions and odds for one kind of `Die` indexed by the amount of dice.
+struct Table {
+    distributions: Vec<Vec<f64>>,
+
+    /// Odds of the attacker rolling higher and of both rolling the same.
+    odds: [[(f64, f64); SIZE]; SIZE],
+}
+
+impl Table {
+    fn new(die: Die) -> Self {
+        let distributions: Vec<Vec<f64>> = (0..SIZE as u8)
+            .map(|dice| calc_distribution(dice, die))
+            .collect();
+        let mut odds = [[(0_f64, 0_f64); SIZE]; SIZE];
+
+        for attacker in 0..SIZE {
+            for defender in 0..SIZE {
+                odds[attacker][defender] = calc_odds(
+                    &distributions[attacker], &distributions[defender],
+                );
+            }
+        }
+
+        Table { distributions, odds }
+    }
+}
+
+fn table(die: Die) -> Rc<Table> {
+    TABLES.with(|tables| {
+        tables
+            .borrow_mut()
+            .entry(die)
+            .or_insert_with(|| Rc::new(Table::new(die)))
+            .clone()
+    })
+}
+
+/// How likely each total is when rolling `dice` amount of `die`. Indexed by the total.
+fn calc_distribution(dice: u8, die: Die) -> Vec<f64> {
+    let faces = die.faces() as usize;
+    (0..dice).fold(vec![1_f64], |distribution, _| {
+        let mut next = vec![0_f64; distribution.len() + faces];
+        for (total, odds) in distribution.iter().enumerate() {
+            for face in 1..=faces {
+                next[total + face] += odds / faces as f64;
+            }
+        }
+        next
+    })
+}
+
+/// Odds of the `attacker` distribution rolling higher than, and the same as, the
+/// `defender` distribution.
+fn calc_odds(attacker: &[f64], defender: &[f64]) -> (f64, f64) {
+    attacker
+        .iter()
+        .enumerate()
+        .fold((0_f64, 0_f64), |(higher, same), (a_total, a_odds)| {
+            let beaten: f64 = defender.iter().take(a_total).sum();
+            let matched = defender.get(a_total).cloned().unwrap_or(0_f64);
+            (higher + a_odds * beaten, same + a_odds * matched)
+        })
+}
+
+/// Apply the `tie` rule to the odds of rolling higher and rolling the same.
+fn resolve_tie((higher, same): (f64, f64), tie: &TieRule) -> f64 {
+    match tie {
+        TieRule::DefenderWins => higher,
+        TieRule::AttackerWins => higher + same,
+        TieRule::Reroll => if same < 1_f64 { higher / (1_f64 - same) } else { 0_f64 },
+    }
+}
+
+/// The probability of rolling each total with `dice` amount of `die`, indexed by the total.
+pub fn sum_distribution(dice: u8, die: Die) -> Vec<f64> {
+    if dice as usize >= SIZE {
+        return calc_distribution(dice, die);
+    }
+    table(die).distributions[dice as usize].clone()
+}
+
+/// Probability of `attacker` amount of D6's rolling strictly higher than `defender` D6's.
+/// This is how `Session::advance` decides attacks by default.
+pub fn beats(attacker: u8, defender: u8) -> f64 {
+    attack_odds(attacker, defender, Die::D6, &TieRule::DefenderWins)
+}
+
+/// Probability of the attacker winning with the `tie` rule deciding equal rolls.
+pub fn attack_odds(attacker: u8, defender: u8, die: Die, tie: &TieRule) -> f64 {
+    let (a, d) = (attacker as usize, defender as usize);
+    let odds = if a >= SIZE || d >= SIZE {
+        calc_odds(&calc_distribution(attacker, die), &calc_distribution(defender, die))
+    } else {
+        table(die).odds[a][d]
+    };
+
+    resolve_tie(odds, tie)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn distributions() {
+        let two = sum_distribution(2, Die::D6);
+
+        assert!(two.len() == 13);
+        assert!(close(two[7], 6_f64 / 36_f64));
+        assert!(close(two[2], 1_f64 / 36_f64));
+        assert!(close(two.iter().sum(), 1_f64));
+        assert!(close(sum_distribution(9, Die::D6).iter().sum(), 1_f64));
+        assert!(close(sum_distribution(3, Die::D20).iter().sum(), 1_f64));
+    }
+
+    #[test]
+    fn known_odds() {
+        assert!(close(beats(1, 1), 15_f64 / 36_f64));
+        assert!(close(attack_odds(1, 1, Die::D6, &TieRule::AttackerWins), 21_f64 / 36_f64));
+        assert!(close(beats(2, 1), 0.837962962962963));
+        assert!(beats(1, 7) == 0_f64);
+        assert!(close(beats(8, 8), resolve_tie(
+            calc_odds(&calc_distribution(8, Die::D6), &calc_distribution(8, Die::D6)),
+            &TieRule::DefenderWins,
+        )));
+    }
+
+    #[test]
+    fn other_dice() {
+        assert!(close(attack_odds(1, 1, Die::D4, &TieRule::DefenderWins), 6_f64 / 16_f64));
+        assert!(close(attack_odds(1, 1, Die::D20, &TieRule::DefenderWins), 0.475));
+
+        // Re-rolling ties makes an even fight a coin toss.
+        assert!(close(attack_odds(2, 2, Die::D8, &TieRule::Reroll), 0.5));
+        assert!(attack_odds(0, 0, Die::D6, &TieRule::Reroll) == 0_f64);
+    }
+}
@@ -0,0 +1,83 @@
+//! Final placings of the players when a game ends without a single player taking the
+//! whole board.
+use std::fmt;
+
+use derive_getters::Getters;
+
+use super::Player;
+
+/// Players in order of their place. Players on equal points share a place and the next
+/// place down is skipped, so two players sharing first are followed by third.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct Ranking {
+    /// Each player with their place starting from 1 for first.
+    placings: Vec<(Player, usize)>,
+}
+
+impl Ranking {
+    /// Rank the players by their `points`. The most points comes first. Players on equal
+    /// points keep the order they were given in.
+    pub fn from_points(mut points: Vec<(Player, usize)>) -> Self {
+        points.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut placings: Vec<(Player, usize)> = Vec::with_capacity(points.len());
+        for (index, (player, player_points)) in points.iter().enumerate() {
+            let place = match index.checked_sub(1).map(|prev| points[prev].1) {
+                Some(prev_points) if prev_points == *player_points => placings[index - 1].1,
+                _ => index + 1,
+            };
+            placings.push((*player, place));
+        }
+
+        Ranking { placings }
+    }
+
+    /// The sole player in first place. `None` if first place is shared.
+    pub fn winner(&self) -> Option<Player> {
+        let mut first = self.placings.iter().filter(|(_, place)| *place == 1);
+        match (first.next(), first.next()) {
+            (Some((player, _)), None) => Some(*player),
+            _ => None,
+        }
+    }
+
+    /// All the ranked players from first to last.
+    pub fn players(&self) -> Vec<Player> {
+        self.placings
+            .iter()
+            .map(|(player, _)| *player)
+            .collect()
+    }
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let placings = self.placings
+            .iter()
+            .map(|(player, place)| format!("{}. {}", place, player))
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}", &placings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shared_places() {
+        let a = Player::new(1, 'A');
+        let b = Player::new(2, 'B');
+        let c = Player::new(3, 'C');
+
+        let ranking = Ranking::from_points(vec![(a, 2), (b, 5), (c, 2)]);
+        assert!(ranking.placings() == &vec![(b, 1), (a, 2), (c, 2)]);
+        assert!(ranking.winner() == Some(b));
+
+        let ranking = Ranking::from_points(vec![(a, 4), (b, 4), (c, 1)]);
+        assert!(ranking.placings() == &vec![(a, 1), (b, 1), (c, 3)]);
+        assert!(ranking.winner().is_none());
+        assert!(format!("{}", ranking) == "1. A, 1. B, 3. C");
+    }
+}
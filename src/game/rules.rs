@@ -1,22 +1,189 @@
 //! Game rules. Controls what are valid moves.
 
+use std::collections::HashMap;
+
 use crate::hexagon::{Grid, Cube};
 use super::model::*;
-use super::Player;
+use super::{Player, Players};
+use super::probability;
+use super::ranking::Ranking;
+use super::ruleset::{
+    Ruleset, PassRule, FailurePenalty, AttackRule, Reinforcement, Placement, TieBreaker,
+    CapitalLoss, Resignation,
+};
+
+/// Calculate all valid moves for the `board` according to the `ruleset`.
+pub (in crate::game) fn choices_from_board<H: Holding>(
+    board: &Board<H>, ruleset: &Ruleset,
+) -> Vec<Choice<H>> {
+    // Objectives are tallied at the end of a turn so the game can be won on points before
+    // the next player gets to move.
+    if points_winner(board, ruleset).is_some() {
+        let consequence = Consequence::VictoryPoints(board.to_owned());
+        return vec![Choice::new(Action::Pass, consequence)];
+    }
+
+    // A manual placement phase has to be finished before anything else.
+    if *board.reinforcing() > 0 {
+        return placement_choices(board, ruleset);
+    }
+
+    match ruleset.pass() {
+        PassRule::OnlyAtEnd => choices_from_board_only_pass_at_end(board, ruleset),
+        PassRule::Anytime => choices_from_board_pass_anytime(board, ruleset),
+    }
+}
+
+/// Like `choices_from_board_only_pass_at_end` but a passing move is always appended to the
+/// attacking moves so a player can end their turn early.
+pub (in crate::game) fn choices_from_board_pass_anytime<H: Holding>(
+    board: &Board<H>, ruleset: &Ruleset,
+) -> Vec<Choice<H>> {
+    let mut choices = choices_from_board_only_pass_at_end(board, ruleset);
+
+    // If there's a pass already, it's either the end of the turn or the game.
+    let has_pass = choices
+        .iter()
+        .any(|choice| *choice.action() == Action::Pass);
+
+    if !has_pass {
+        choices.push(end_turn(board, ruleset));
+    }
+
+    choices
+}
+
+/// Whether `player` holds the hex. Water can't be played with so it's never held.
+fn holds<H: Holding>(hold: &H, player: Player) -> bool {
+    hold.owner() == player && hold.terrain().passable()
+}
+
+/// The most dice a hex can have under the `ruleset` without overflowing the holding.
+/// Hexes with a capacity of their own go by `capacity` instead.
+fn max_dice<H: Holding>(ruleset: &Ruleset) -> u8 {
+    (*ruleset.max_dice()).min(H::DICE_LIMIT)
+}
+
+/// The most dice `hold` can take. Its own capacity if it has one, otherwise `max_dice`.
+fn capacity<H: Holding>(hold: &H, max_dice: u8) -> u8 {
+    hold.capacity().unwrap_or(max_dice).min(H::DICE_LIMIT)
+}
+
+/// Pass the turn to the next player granting the current player their reinforcements. With
+/// `Placement::Manual` the turn continues into a placement phase instead.
+fn end_turn<H: Holding>(board: &Board<H>, ruleset: &Ruleset) -> Choice<H> {
+    let player = board.players().current();
+    let amount = ruleset
+        .handicap(player)
+        .reinforce(reinforcement_amount(board, ruleset.reinforcement()))
+        .saturating_add(board.reserve(player));
 
-/// Maximum amount of dice a hexagon holding may have.
-const MAX_DICE: u8 = 5;
+    // The reserve is drawn down into the placement phase in full. Whatever can't be
+    // placed goes back into it when the phase is over.
+    if *ruleset.placement() == Placement::Manual && amount > 0 {
+        let new_board = board.next(*board.players(), board.grid().to_owned(), 0, 0)
+            .with_reserve(player, 0)
+            .with_reinforcing(amount);
+        return Choice::new(Action::Pass, Consequence::Continue(new_board));
+    }
+
+    // The tree can't roll for random placements so it always picks the first hex.
+    let new_board = board_from_pass(board, ruleset, &mut |_| 0);
+    Choice::new(Action::Pass, Consequence::TurnOver(new_board))
+}
+
+/// The board after the current player passes their turn and has their reinforcements
+/// placed. `pick` chooses an index below the amount it's given and is only used for
+/// `Placement::Random`. `Placement::Manual` falls back to `Placement::TopDown` here. The
+/// player's reserve is added to the reinforcements and whatever doesn't fit is banked.
+pub fn board_from_pass<H: Holding>(
+    board: &Board<H>, ruleset: &Ruleset, pick: &mut dyn FnMut(usize) -> usize,
+) -> Board<H> {
+    let player = board.players().current();
+    let amount = ruleset
+        .handicap(player)
+        .reinforce(reinforcement_amount(board, ruleset.reinforcement()))
+        .saturating_add(board.reserve(player));
+    let max_dice = max_dice::<H>(ruleset);
+    let capital = board.capital(player);
+    let space = match ruleset.placement() {
+        Placement::SupplyLines => supplied_room(board.grid(), player, capital, max_dice),
+        _ => room(board.grid(), player, max_dice),
+    };
+    let leftover = amount.saturating_sub(space);
+
+    let new_grid = place_reinforcements(
+        board.grid(),
+        player,
+        capital,
+        amount,
+        max_dice,
+        ruleset.placement(),
+        pick,
+    );
+    let new_board = board.next(board.players().next(), new_grid, 0, 0)
+        .with_reserve(player, leftover.min(*ruleset.max_reserve()));
+    score_objectives(new_board, player)
+}
+
+/// All the hexes the current player can place their next reinforcement die on. Placing
+/// the last die ends the turn. If there's no room left anywhere the remaining dice are
+/// banked as far as the reserve allows and the turn is passed.
+fn placement_choices<H: Holding>(board: &Board<H>, ruleset: &Ruleset) -> Vec<Choice<H>> {
+    let player = board.players().current();
+    let remaining = *board.reinforcing() - 1;
+    let max_dice = max_dice::<H>(ruleset);
+
+    let choices: Vec<Choice<H>> = board
+        .grid()
+        .iter()
+        .filter(|ht| {
+            holds(ht.data(), player) && ht.data().dice() < capacity(ht.data(), max_dice)
+        })
+        .map(|ht| {
+            let action = Action::Reinforce(*ht.coordinate());
+            let new_grid = grid_from_move(board.grid(), action);
+            let consequence = if remaining == 0 {
+                let new_board = board.next(
+                    board.players().next(), unfreeze(&new_grid, player), 0, 0,
+                );
+                Consequence::TurnOver(score_objectives(new_board, player))
+            } else {
+                Consequence::Continue(
+                    board.next(*board.players(), new_grid, 0, 0)
+                        .with_reinforcing(remaining)
+                )
+            };
+            Choice::new(action, consequence)
+        })
+        .collect();
+
+    if choices.is_empty() {
+        let banked = board.reserve(player)
+            .saturating_add(*board.reinforcing())
+            .min(*ruleset.max_reserve());
+        let new_board = board.next(
+            board.players().next(), unfreeze(board.grid(), player), 0, 0,
+        )
+            .with_reserve(player, banked);
+        let new_board = score_objectives(new_board, player);
+        return vec![Choice::new(Action::Pass, Consequence::TurnOver(new_board))];
+    }
+
+    choices
+}
 
 /// Calculated all valid moves except the passing move until there are no
 /// attacking moves left. This greatly reduces the tree branches.
-pub (in crate::game) fn choices_from_board_only_pass_at_end(
-    board: &Board, move_limit: u8,
-) -> Vec<Choice> {
+pub (in crate::game) fn choices_from_board_only_pass_at_end<H: Holding>(
+    board: &Board<H>, ruleset: &Ruleset,
+) -> Vec<Choice<H>> {
     let attacking_moves = all_legal_attacks_from(
-        board.grid(), &board.players().current()
+        board.grid(), board.players(), ruleset.attack(),
     );
+    let move_limit = ruleset.move_limit().get();
 
-    let mut choices: Vec<Choice> = Vec::new();
+    let mut choices: Vec<Choice<H>> = Vec::new();
     let moved = *board.moved() + 1;
     //println!("Calculating.... Moved: {}, Limit: {}", &moved, &move_limit);
 
@@ -30,34 +197,42 @@ pub (in crate::game) fn choices_from_board_only_pass_at_end(
         // Next we check if the player has been knocked out.
         if loser(board) {
             let new_grid = grid_from_move(board.grid(), Action::Pass);
-            let new_board = Board::new(
-                board.players().remove_current(), new_grid, 0, 0
-            );
+            let new_board = board.next(board.players().remove_current(), new_grid, 0, 0);
             return vec![Choice::new(Action::Pass, Consequence::GameOver(new_board))];
         }
 
         // Lastly, we check if the game has been locked in a stalemate. This also ends
-        // the game but there is no winner. We haven't yet implemented scoring to determine
-        // a winner by points or a tie-breaker.
-        if stalemate(board) {
+        // the game. The players are then ranked with `rank_players`.
+        if stalemate(board, ruleset.attack()) {
             return vec![
                 Choice::new(Action::Pass, Consequence::Stalemate(board.to_owned()))
             ];
         }   
 
         // Since there is not winner or knockout. We add a passing move.
-        let new_grid = reinforce02(
-            board.grid(), board.players().current(), *board.captured_dice(),
-        );
-        let new_board = Board::new(board.players().next(), new_grid, 0, 0);
-        choices.push(Choice::new(Action::Pass, Consequence::TurnOver(new_board))); 
+        choices.push(end_turn(board, ruleset));
     } else if moved > move_limit {
         // If we have exceeded the move limit, we pass.
-        let new_grid = reinforce02(
-            board.grid(), board.players().current(), *board.captured_dice(),
+        return vec![end_turn(board, ruleset)];
+    }
+
+    // Shuffling dice about doesn't count as a move. It has its own limit.
+    let transferred = *board.transferred();
+    if transferred < *ruleset.transfer_limit() {
+        choices.extend(
+            all_legal_transfers_from(
+                board.grid(), &board.players().current(), max_dice::<H>(ruleset),
+            )
+                .into_iter()
+                .map(|transfer| {
+                    let new_grid = grid_from_move(board.grid(), transfer);
+                    let new_board = board.next(
+                        *board.players(), new_grid, *board.captured_dice(), *board.moved(),
+                    )
+                        .with_transferred(transferred + 1);
+                    Choice::new(transfer, Consequence::Continue(new_board))
+                })
         );
-        let new_board = Board::new(board.players().next(), new_grid, 0, 0);
-        return vec![Choice::new(Action::Pass, Consequence::TurnOver(new_board))];
     }
 
     // Process attacking moves. This is functionally skipped if there are none.
@@ -67,28 +242,53 @@ pub (in crate::game) fn choices_from_board_only_pass_at_end(
             .into_iter()
             .map(|attack| {
                 let new_grid = grid_from_move(board.grid(), attack);
+                let fallen = fallen_capital(board, attack);
+                let new_grid = match fallen {
+                    Some(defender) => {
+                        let captor = match ruleset.capital_loss() {
+                            CapitalLoss::ToCaptor => board.players().current(),
+                            CapitalLoss::Neutral => Player::neutral(),
+                        };
+                        hand_over(&new_grid, defender, captor)
+                    },
+                    None => new_grid,
+                };
                 let total_captured = captured_dice + attack.capturing();
-                let new_board = Board::new(
-                    *board.players(), new_grid, total_captured, moved,
+                let players = knock_out(&new_grid, board.players());
+                let new_board = board.next(players, new_grid, total_captured, moved)
+                    .with_transferred(transferred);
+                let new_board = match fallen {
+                    Some(defender) => new_board.with_capital(defender, None),
+                    None => new_board,
+                };
+                let failed_board = board_from_failed_attack(
+                    board, attack, *ruleset.failure_penalty(),
+                );
+                let chance = Chance::new(
+                    win_probability(board.grid(), attack, ruleset),
+                    Consequence::Continue(failed_board),
                 );
-                Choice::new(attack, Consequence::Continue(new_board))
+                Choice::new(attack, Consequence::Continue(new_board)).with_chance(chance)
             })
-            .collect::<Vec<Choice>>()
+            .collect::<Vec<Choice<H>>>()
     );
 
     choices
 }
 
-/// Iterates through the entire board to see if they are all owned by the current player
-/// in the `BoardState`. If so, we have a winner. This function should only be called when
-/// there are no attacking moves possible from the same `BoardState` being fed in.
-fn winner(board: &Board) -> bool {
+/// Iterates through the entire board to see if every hex, ignoring water and neutral hexes,
+/// is owned by the current player or their team in the `BoardState`. If so, we have a
+/// winner. This function should only be called when there are no attacking moves possible
+/// from the same `BoardState` being fed in.
+fn winner<H: Holding>(board: &Board<H>) -> bool {
     let player = board.players().current();
     board
         .grid()
         .iter()
         .try_for_each(|ht| {
-            if ht.data().owner() == player {
+            let hold = ht.data();
+            let ignored = !hold.terrain().passable() || hold.owner().is_neutral();
+            if ignored || board.players().allies(hold.owner(), player) {
                 Ok(())
             } else {
                 Err(())
@@ -97,14 +297,57 @@ fn winner(board: &Board) -> bool {
         .is_ok()
 }
 
+/// The first player still in the game with the victory points the `ruleset` asks for.
+/// Always `None` when winning on points is disabled.
+fn points_winner<H: Holding>(board: &Board<H>, ruleset: &Ruleset) -> Option<Player> {
+    let target = *ruleset.victory_points();
+    if target == 0 {
+        return None;
+    }
+
+    board
+        .players()
+        .playing()
+        .into_iter()
+        .find(|player| board.points(*player) >= target)
+}
+
+/// The player still in the game with the most victory points. Ties favour the current
+/// player. On a board from `Consequence::VictoryPoints` this is the winner.
+pub fn points_leader<H: Holding>(board: &Board<H>) -> Player {
+    board
+        .players()
+        .playing()
+        .into_iter()
+        .fold(board.players().current(), |leader, player| {
+            if board.points(player) > board.points(leader) {
+                player
+            } else {
+                leader
+            }
+        })
+}
+
+/// Credit `player` with a victory point for every objective they hold on the `board`.
+fn score_objectives<H: Holding>(board: Board<H>, player: Player) -> Board<H> {
+    let held = board
+        .grid()
+        .iter()
+        .filter(|ht| holds(ht.data(), player) && ht.data().objective())
+        .count();
+    let held = held.min(u8::max_value() as usize) as u8;
+    let points = board.points(player).saturating_add(held);
+    board.with_points(player, points)
+}
+
 /// A repeat of `winner` above. Should be able to check for either within the same iter.
-fn loser(board: &Board) -> bool {
+fn loser<H: Holding>(board: &Board<H>) -> bool {
     let player = board.players().current();
     board
         .grid()
         .iter()
         .try_for_each(|ht| {
-            if ht.data().owner() != player {
+            if !holds(ht.data(), player) {
                 Ok(())
             } else {
                 Err(())
@@ -115,13 +358,18 @@ fn loser(board: &Board) -> bool {
 
 /// Check if the board is in a statelmate condition. This means that there is more than
 /// one player and no player can attack another player. Once a stalemate has been detected,
-/// then we can layer on calculation as to whether it's a draw or win by points.
-fn stalemate(board: &Board) -> bool {
+/// then we can layer on calculation as to whether it's a draw or win by points. Neutral
+/// hexes never attack but can still be attacked.
+fn stalemate<H: Holding>(board: &Board<H>, attack: &AttackRule) -> bool {
     // Special case for boards consisting of a single or no hex tile. They cant be in
     // stalemate at all, it's impossible.
     if board.grid().len() < 2 {
         return false;
     }
+
+    // Neutral hexes and those of players who are out never attack.
+    let playing = board.players().playing();
+    let attacks = |player: Player| playing.contains(&player);
     
     board
         .grid()
@@ -129,6 +377,11 @@ fn stalemate(board: &Board) -> bool {
         .try_for_each(|ht| {
             let hold = *ht.data();
             let coordinate = *ht.coordinate();
+
+            // Nothing happens on water.
+            if !hold.terrain().passable() {
+                return Ok(());
+            }
             
             coordinate
                 .three_neighbours()
@@ -137,15 +390,19 @@ fn stalemate(board: &Board) -> bool {
                     board
                         .grid()
                         .fetch(neighbour)
-                        .map(|d| Some(d))
+                        .map(|d| Some(d).filter(|d| d.terrain().passable()))
                         .or(Ok(None))
                         .and_then(|maybie| {
                             if let Some(other) = maybie {
-                                // Check if the other tile is held by another.
-                                if other.owner() != hold.owner() {
-                                    // If so, we check if an attack is ever possible. This
-                                    // means that either one must be more than 1.
-                                    if other.dice() > 1 || hold.dice() > 1 {
+                                // Check if the other tile is held by another team.
+                                if !board.players().allies(other.owner(), hold.owner()) {
+                                    // If so, we check if an attack is ever possible
+                                    // in either direction.
+                                    let forwards = attacks(hold.owner())
+                                        && attack.allows(hold.dice(), other.dice());
+                                    let backwards = attacks(other.owner())
+                                        && attack.allows(other.dice(), hold.dice());
+                                    if forwards || backwards {
                                         // An attack is possible. Short-circuit out.
                                         Err(())
                                     } else {
@@ -157,7 +414,7 @@ fn stalemate(board: &Board) -> bool {
                                     Ok(())
                                 }
                             } else {
-                                // No neighbour tile. Impossible to attack.
+                                // No neighbour tile or only water. Impossible to attack.
                                 Ok(())
                             }
                         })
@@ -168,14 +425,46 @@ fn stalemate(board: &Board) -> bool {
 }
 
 /// Produces all legal attacking moves with the amount of dice they would capture.
-fn all_legal_attacks_from(grid: &Grid<u8>, player: &Player) -> Vec<Action> {
+/// Every way `player` can move dice from a mobile hex into a neighbouring hex of their
+/// own. At least one die always stays behind and the receiving hex can't go over its
+/// capacity.
+fn all_legal_transfers_from<H: Holding>(
+    grid: &Grid<H>, player: &Player, max_dice: u8,
+) -> Vec<Action> {
+    let mut transfers = Vec::new();
+    for hex_tile in grid.iter() {
+        let from = *hex_tile.coordinate();
+        let hold = *hex_tile.data();
+        if !holds(&hold, *player) || !hold.mobile() || hold.dice() < 2 {
+            continue;
+        }
+
+        for to in from.neighbours().iter() {
+            let room = match grid.fetch(to) {
+                Ok(d) if holds(d, *player) => {
+                    capacity(d, max_dice).saturating_sub(d.dice())
+                },
+                _ => continue,
+            };
+            let most = room.min(hold.dice() - 1);
+            transfers.extend((1..=most).map(|count| Action::Transfer(from, *to, count)));
+        }
+    }
+    transfers
+}
+
+/// Every attack the current player can make on a hex of a player outside their team.
+fn all_legal_attacks_from<H: Holding>(
+    grid: &Grid<H>, players: &Players, attack: &AttackRule,
+) -> Vec<Action> {
+    let player = &players.current();
     grid.iter()
         .fold(Vec::new(), |mut moves, hex_tile| {
             //dbg!(hex_tile);
             let coordinate = *hex_tile.coordinate();
             let hold = *hex_tile.data();
 
-            if hold.owner() == *player && hold.mobile() {
+            if holds(&hold, *player) && hold.mobile() {
                 moves.extend(
                     coordinate
                         .neighbours()
@@ -184,12 +473,13 @@ fn all_legal_attacks_from(grid: &Grid<u8>, player: &Player) -> Vec<Action> {
                             //dbg!(neighbour);
                             grid.fetch(neighbour)
                                 .ok() // Ignore the misses
+                                .filter(|d| d.terrain().passable()) // And the water
                                 .and_then(|d| {
                                     //dbg!(d);
-                                    if d.owner() != *player {
+                                    if !players.allies(d.owner(), *player) {
                                         // We have an enemy tile. We count dice.
-                                        if hold.dice() > 1 && d.dice() <= hold.dice() {
-                                            // Player has more dice! 
+                                        if attack.allows(hold.dice(), d.dice()) {
+                                            // Player has enough dice!
                                             Some(Action::Attack(
                                                 coordinate,
                                                 *neighbour,
@@ -215,36 +505,379 @@ fn all_legal_attacks_from(grid: &Grid<u8>, player: &Player) -> Vec<Action> {
 
 /// Generates a new grid that bears the consequences of the supplied movement. Doesn't
 /// check if the move is legal.
-fn grid_from_move(grid: &Grid<u8>, movement: Action) -> Grid<u8> {
+fn grid_from_move<H: Holding>(grid: &Grid<H>, movement: Action) -> Grid<H> {
     match movement {
-        Action::Pass => grid.to_owned(),
+        // Resigning depends on the ruleset. See `board_from_resign`.
+        Action::Pass | Action::Resign(_) => grid.to_owned(),
         Action::Attack(from, to, _, _) => attacking_move(grid, from, to),
+        Action::Transfer(from, to, count) => grid.fork_with(|cube, hold| {
+            if cube == &from {
+                hold.update(hold.owner(), hold.dice() - count, hold.mobile())
+            } else if cube == &to {
+                hold.update(hold.owner(), hold.dice() + count, hold.mobile())
+            } else {
+                hold
+            }
+        }),
+        Action::Reinforce(hex) => grid.fork_with(|cube, hold| {
+            if cube == &hex {
+                hold.update(hold.owner(), hold.dice() + 1, hold.mobile())
+            } else {
+                hold
+            }
+        }),
     }
 }
 
+/// The board after `player` resigns. They're out of the game straight away and their
+/// hexes are dealt with according to the `Resignation` rule. If it was their turn, the turn
+/// passes to the next player without any reinforcements.
+pub fn board_from_resign<H: Holding>(
+    board: &Board<H>, player: Player, ruleset: &Ruleset,
+) -> Board<H> {
+    let new_grid = board.grid().fork_with(|_, hold| {
+        if hold.owner() != player {
+            hold
+        } else {
+            match ruleset.resignation() {
+                Resignation::Neutral => hold.update(Player::neutral(), hold.dice(), true),
+                Resignation::Frozen => hold.update(player, hold.dice(), false),
+            }
+        }
+    });
+
+    let players = board.players().remove(player);
+    let new_board = if board.players().current() == player {
+        board.next(players, new_grid, 0, 0)
+    } else {
+        board.next(players, new_grid, *board.captured_dice(), *board.moved())
+            .with_transferred(*board.transferred())
+            .with_reinforcing(*board.reinforcing())
+    };
+
+    new_board
+        .with_reserve(player, 0)
+        .with_capital(player, None)
+}
+
 /// An attacking move that removes all the dice except one from the `from` hexagon and
 /// places them minus one to the `to` tile. There is no error checking as this function
-/// expects correct parameters to be entered. Thus invalid data will cause a panic.
-fn attacking_move(grid: &Grid<u8>, from: Cube, to: Cube) -> Grid<u8> {
-    let (to_hold, from_hold) = grid
-        .fetch(&from)
-        .map(|h| (
-            u8::new(h.owner(), h.dice() - 1, h.mobile()),
-            u8::new(h.owner(), 1, h.mobile())
-        ))
-        .expect("Invalid from coordinate.");
+/// expects correct parameters to be entered. Thus invalid data will cause a panic. Both
+/// hexes keep their terrain. Dice past the capacity of the `to` hex stay behind.
+fn attacking_move<H: Holding>(grid: &Grid<H>, from: Cube, to: Cube) -> Grid<H> {
+    let attacker = *grid.fetch(&from).expect("Invalid from coordinate.");
+    let defender = *grid.fetch(to).expect("Invalid to coordinate.");
+    let moved = (attacker.dice() - 1).min(capacity(&defender, H::DICE_LIMIT));
 
     grid.fork_with(|cube, hold| {
         if cube == &from {
-            from_hold
+            hold.update(attacker.owner(), attacker.dice() - moved, attacker.mobile())
         } else if cube == &to {
-            to_hold
+            hold.update(attacker.owner(), moved, attacker.mobile())
+        } else {
+            hold
+        }
+    })
+}
+
+/// The player whose capital is taken if the `attack` succeeds.
+fn fallen_capital<H: Holding>(board: &Board<H>, attack: Action) -> Option<Player> {
+    let to = match attack {
+        Action::Attack(_, to, _, _) => to,
+        _ => return None,
+    };
+
+    board
+        .grid()
+        .fetch(to)
+        .ok()
+        .map(|hold| hold.owner())
+        .filter(|defender| board.capital(*defender) == Some(to))
+}
+
+/// Every hex of `defender` goes over to `captor`, who may be the neutral player. They're
+/// frozen until the captor's next turn. Leaves `defender` to be knocked out when their
+/// turn comes around.
+fn hand_over<H: Holding>(grid: &Grid<H>, defender: Player, captor: Player) -> Grid<H> {
+    grid.fork_with(|_, hold| {
+        if hold.owner() == defender {
+            hold.update(captor, hold.dice(), false)
+        } else {
+            hold
+        }
+    })
+}
+
+/// `players` less anyone left without a hex on the `grid`. They go out on the turn being
+/// played, in the order they're numbered.
+fn knock_out<H: Holding>(grid: &Grid<H>, players: &Players) -> Players {
+    players
+        .playing()
+        .into_iter()
+        .filter(|player| grid.iter().all(|ht| !holds(ht.data(), *player)))
+        .fold(*players, |players, player| players.remove(player))
+}
+
+/// Produce the board after the `attack` has failed. The board stays the same sans one
+/// move with the attacking hex frozen and penalized. Passing moves return the board as is.
+pub fn board_from_failed_attack<H: Holding>(
+    board: &Board<H>, attack: Action, penalty: FailurePenalty,
+) -> Board<H> {
+    let (from, defender_dice) = match attack {
+        Action::Attack(from, _, _, defender_dice) => (from, defender_dice),
+        _ => return board.to_owned(),
+    };
+
+    let grid = board
+        .grid()
+        .fork_with(|coordinate, hold| {
+            if coordinate == &from {
+                let dice = match penalty {
+                    FailurePenalty::Freeze => hold.dice(),
+                    FailurePenalty::OneDie => 1,
+                    FailurePenalty::LoseDefenderDice => {
+                        hold.dice().saturating_sub(defender_dice).max(1)
+                    },
+                };
+                hold.update(hold.owner(), dice, false)
+            } else {
+                hold
+            }
+        });
+
+    board.next(*board.players(), grid, *board.captured_dice(), *board.moved() + 1)
+        .with_transferred(*board.transferred())
+}
+
+/// Exact probability of the `attack` on the `grid` succeeding with the dice and ties of the
+/// `ruleset`. The defender's terrain is taken into account.
+pub (in crate::game) fn win_probability<H: Holding>(
+    grid: &Grid<H>, attack: Action, ruleset: &Ruleset,
+) -> f64 {
+    let (attacker_dice, defender_dice) = match attack {
+        Action::Attack(_, to, ad, dd) => (ad, dd + defence_bonus(grid, to)),
+        _ => return 0_f64,
+    };
+
+    probability::attack_odds(attacker_dice, defender_dice, *ruleset.die(), ruleset.tie())
+}
+
+/// Extra dice rolled by the defender of the hex `to` thanks to its terrain.
+pub fn defence_bonus<H: Holding>(grid: &Grid<H>, to: Cube) -> u8 {
+    grid.fetch(to)
+        .map(|hold| hold.terrain().defence_bonus())
+        .unwrap_or(0)
+}
+
+/// Rank the players still in the game by points according to the `tie_breaker`.
+pub fn rank_players<H: Holding>(board: &Board<H>, tie_breaker: &TieBreaker) -> Ranking {
+    let grid = board.grid();
+    let points = board
+        .players()
+        .playing()
+        .into_iter()
+        .map(|player| {
+            let owned = grid.iter().filter(|ht| holds(ht.data(), player));
+            let points = match tie_breaker {
+                TieBreaker::MostHexes => owned.count(),
+                TieBreaker::MostDice => owned.map(|ht| ht.data().dice() as usize).sum(),
+                TieBreaker::LargestRegion => largest_region(grid, player),
+            };
+            (player, points)
+        })
+        .collect();
+
+    Ranking::from_points(points)
+}
+
+/// Size of the largest group of connected hexes owned by `player`. Water doesn't connect.
+pub (in crate::game) fn largest_region<H: Holding>(
+    grid: &Grid<H>, player: Player,
+) -> usize {
+    player_regions(grid, player)
+        .into_iter()
+        .map(|region| region.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Each group of connected hexes held by `player`.
+fn player_regions<H: Holding>(grid: &Grid<H>, player: Player) -> Vec<Vec<Cube>> {
+    grid.regions(|a, b| {
+        a.owner() == b.owner() && a.terrain().passable() && b.terrain().passable()
+    })
+        .into_iter()
+        .filter(|region| {
+            grid.fetch(region[0])
+                .map(|hold| holds(hold, player))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Hexes of `player` connected to their largest region or to their `capital`. Ties for the
+/// largest region go to the one found first.
+fn supplied_hexes<H: Holding>(
+    grid: &Grid<H>, player: Player, capital: Option<Cube>,
+) -> Vec<Cube> {
+    let regions = player_regions(grid, player);
+    let largest = regions
+        .iter()
+        .enumerate()
+        .fold(None, |largest: Option<(usize, usize)>, (index, region)| match largest {
+            Some((_, size)) if size >= region.len() => largest,
+            _ => Some((index, region.len())),
+        })
+        .map(|(index, _)| index);
+
+    regions
+        .into_iter()
+        .enumerate()
+        .filter(|(index, region)| {
+            Some(*index) == largest || capital.is_some_and(|c| region.contains(&c))
+        })
+        .flat_map(|(_, region)| region)
+        .collect()
+}
+
+/// Like `room` but only counting the hexes of `player` that are in supply.
+fn supplied_room<H: Holding>(
+    grid: &Grid<H>, player: Player, capital: Option<Cube>, max_dice: u8,
+) -> u8 {
+    supplied_hexes(grid, player, capital)
+        .into_iter()
+        .filter_map(|cube| grid.fetch(cube).ok())
+        .map(|hold| capacity(hold, max_dice).saturating_sub(hold.dice()) as usize)
+        .sum::<usize>()
+        .min(u8::max_value() as usize) as u8
+}
+
+/// How many dice the current player has earned at the end of their turn.
+fn reinforcement_amount<H: Holding>(board: &Board<H>, reinforcement: &Reinforcement) -> u8 {
+    let captured = *board.captured_dice();
+    match reinforcement {
+        Reinforcement::CapturedMinusOne => captured.saturating_sub(1),
+        Reinforcement::Captured => captured,
+        Reinforcement::LargestRegion => {
+            let largest = largest_region(board.grid(), board.players().current());
+            largest.min(u8::max_value() as usize) as u8
+        },
+    }
+}
+
+/// How many more dice the hexes of `player` can take before they are all full.
+fn room<H: Holding>(grid: &Grid<H>, player: Player, max_dice: u8) -> u8 {
+    grid.iter()
+        .filter(|ht| holds(ht.data(), player))
+        .map(|ht| capacity(ht.data(), max_dice).saturating_sub(ht.data().dice()) as usize)
+        .sum::<usize>()
+        .min(u8::max_value() as usize) as u8
+}
+
+/// Make all of the `player`'s hexes mobile again for their next turn.
+fn unfreeze<H: Holding>(grid: &Grid<H>, player: Player) -> Grid<H> {
+    grid.fork_with(|_, hold| {
+        if hold.owner() == player {
+            hold.update(player, hold.dice(), true)
         } else {
             hold
         }
     })
 }
 
+/// Hexes of `player` that neighbour a hex of another player.
+fn border_hexes<H: Holding>(grid: &Grid<H>, player: Player) -> Vec<Cube> {
+    grid.iter()
+        .filter(|ht| holds(ht.data(), player))
+        .filter(|ht| {
+            ht.coordinate()
+                .neighbours()
+                .iter()
+                .any(|neighbour| {
+                    grid.fetch(neighbour)
+                        .map(|hold| hold.owner() != player && hold.terrain().passable())
+                        .unwrap_or(false)
+                })
+        })
+        .map(|ht| *ht.coordinate())
+        .collect()
+}
+
+/// Hand out the `reinforcements` onto the hexes of `player` according to the `placement`.
+/// Any reinforcements that don't fit are dropped. The `capital` is only needed for
+/// `Placement::SupplyLines`.
+fn place_reinforcements<H: Holding>(
+    grid: &Grid<H>,
+    player: Player,
+    capital: Option<Cube>,
+    reinforcements: u8,
+    max_dice: u8,
+    placement: &Placement,
+    pick: &mut dyn FnMut(usize) -> usize,
+) -> Grid<H> {
+    match placement {
+        Placement::TopDown | Placement::Manual => {
+            reinforce02(grid, player, reinforcements, max_dice)
+        },
+        Placement::SupplyLines => {
+            let supplied = supplied_hexes(grid, player, capital);
+            reinforce03(grid, player, reinforcements, max_dice, &supplied)
+        },
+        Placement::BorderFirst => {
+            let mut order = border_hexes(grid, player);
+            order.extend(
+                grid.iter()
+                    .filter(|ht| holds(ht.data(), player))
+                    .map(|ht| *ht.coordinate())
+                    .filter(|cube| !order.contains(cube))
+                    .collect::<Vec<Cube>>()
+            );
+
+            let mut reinforcements = reinforcements;
+            let mut adds: HashMap<Cube, u8> = HashMap::new();
+            for cube in order {
+                let space = grid
+                    .fetch(cube)
+                    .map(|hold| capacity(hold, max_dice).saturating_sub(hold.dice()))
+                    .unwrap_or(0);
+                let add = space.min(reinforcements);
+                reinforcements -= add;
+                adds.insert(cube, add);
+            }
+
+            grid.fork_with(|cube, hold| {
+                if hold.owner() == player {
+                    let add = adds.get(cube).cloned().unwrap_or(0);
+                    hold.update(player, hold.dice() + add, true)
+                } else {
+                    hold
+                }
+            })
+        },
+        Placement::Random => {
+            let mut grid = unfreeze(grid, player);
+            for _ in 0..reinforcements {
+                let open: Vec<Cube> = grid
+                    .iter()
+                    .filter(|ht| {
+                        holds(ht.data(), player)
+                            && ht.data().dice() < capacity(ht.data(), max_dice)
+                    })
+                    .map(|ht| *ht.coordinate())
+                    .collect();
+
+                if open.is_empty() {
+                    break;
+                }
+
+                let target = open[(pick)(open.len()) % open.len()];
+                grid = grid_from_move(&grid, Action::Reinforce(target));
+            }
+            grid
+        },
+    }
+}
+
 /*
 /// Add reinforcements for the current player on the grid returning a new grid. If
 /// there is no space left (a player hex cannot have more than five dice) then any
@@ -274,21 +907,22 @@ fn reinforce01(grid: &Grid<Hold>, player: Player, reinforcements: u8) -> Grid<Ho
 }
 */
 
-/// Add reinforcements minus 1 for the current player on the grid returning a new grid. If
-/// there is no space left (a player hex cannot have more than five dice) then any
-/// remaining reinforcements are dropped.
+/// Add reinforcements for the current player on the grid returning a new grid. If there
+/// is no space left (a player hex cannot go over its capacity) then any
+/// remaining reinforcements are dropped. The amount is worked out beforehand by the
+/// `Reinforcement` rule.
 ///
 /// The reinforcements will be doled out super simple. It will simply add them from the
 /// top leftmost of any player holdings downwards.
-fn reinforce02(grid: &Grid<u8>, player: Player, reinforcements: u8) -> Grid<u8> {
-    let mut reinforcements = reinforcements
-        .checked_sub(1)
-        .unwrap_or(0);
+fn reinforce02<H: Holding>(
+    grid: &Grid<H>, player: Player, reinforcements: u8, max_dice: u8,
+) -> Grid<H> {
+    let mut reinforcements = reinforcements;
 
     grid.fork_with(|_, hold| {
-        if hold.owner() == player {
+        if holds(&hold, player) {
             let dice = hold.dice();
-            let diff = MAX_DICE - dice;
+            let diff = capacity(&hold, max_dice).saturating_sub(dice);
             let add = if reinforcements > diff {
                 reinforcements -= diff;
                 diff
@@ -297,7 +931,30 @@ fn reinforce02(grid: &Grid<u8>, player: Player, reinforcements: u8) -> Grid<u8>
                 reinforcements = 0;
                 diff
             };
-            u8::new(player, dice + add, true)
+            hold.update(player, dice + add, true)
+        } else {
+            hold
+        }
+    })
+}
+
+/// Like `reinforce02` but only the `supplied` hexes get reinforcements. Hexes cut off from
+/// supply are still made mobile again.
+fn reinforce03<H: Holding>(
+    grid: &Grid<H>, player: Player, reinforcements: u8, max_dice: u8, supplied: &[Cube],
+) -> Grid<H> {
+    let mut reinforcements = reinforcements;
+
+    grid.fork_with(|cube, hold| {
+        if holds(&hold, player) {
+            let dice = hold.dice();
+            let add = if supplied.contains(cube) {
+                capacity(&hold, max_dice).saturating_sub(dice).min(reinforcements)
+            } else {
+                0
+            };
+            reinforcements -= add;
+            hold.update(player, dice + add, true)
         } else {
             hold
         }
@@ -306,6 +963,8 @@ fn reinforce02(grid: &Grid<u8>, player: Player, reinforcements: u8) -> Grid<u8>
 
 #[cfg(test)]
 mod test {
+    use rand::{rngs, SeedableRng};
+
     use crate::hexagon::Rectangular;
     use crate::game::*;
     use super::*;
@@ -337,7 +996,9 @@ mod test {
     #[test]
     fn no_attacking_moves_available() {
         let board = super::super::canned_2x2_start01();
-        let attacks = all_legal_attacks_from(board.grid(), &board.players().current());
+        let attacks = all_legal_attacks_from(
+            board.grid(), board.players(), &AttackRule::default(),
+        );
         
         assert!(attacks.is_empty());
     }
@@ -345,7 +1006,9 @@ mod test {
     #[test]
     fn one_attacking_move_available() {
         let board = super::super::canned_2x2_start02();
-        let attacks = all_legal_attacks_from(board.grid(), &board.players().current());
+        let attacks = all_legal_attacks_from(
+            board.grid(), board.players(), &AttackRule::default(),
+        );
         
         assert!(attacks.len() == 1);
     }
@@ -353,16 +1016,542 @@ mod test {
     #[test]
     fn two_attacking_moves_available() {
         let board = super::super::canned_2x2_start03();
-        let attacks = all_legal_attacks_from(board.grid(), &board.players().current());
+        let attacks = all_legal_attacks_from(
+            board.grid(), board.players(), &AttackRule::default(),
+        );
         
         assert!(attacks.len() == 2);
     }
 
+    #[test]
+    fn attack_rule_respected() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 2, true)),
+            ((1, 0).into(), u8::new(player2, 2, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+
+        let players = Players::new(2);
+        let attacks = all_legal_attacks_from(&grid, &players, &AttackRule::NotOutnumbered);
+        assert!(attacks.len() == 1);
+
+        let attacks = all_legal_attacks_from(&grid, &players, &AttackRule::Outnumbering);
+        assert!(attacks.is_empty());
+    }
+
+    #[test]
+    fn largest_region_reinforcement() {
+        let board = super::super::canned_3x3_start01();
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+
+        assert!(largest_region(board.grid(), player1) == 5);
+        assert!(largest_region(board.grid(), player2) == 2);
+        assert!(reinforcement_amount(&board, &Reinforcement::LargestRegion) == 5);
+        assert!(reinforcement_amount(&board, &Reinforcement::CapturedMinusOne) == 0);
+    }
+
+    #[test]
+    fn border_first_placement() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(player1, 1, true)),
+            ((2, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+
+        let placed = place_reinforcements(
+            &grid, player1, None, 3, 5, &Placement::BorderFirst, &mut |_| 0,
+        );
+        assert!(placed.fetch(Cube::from((0, 0))).unwrap().dice() == 1);
+        assert!(placed.fetch(Cube::from((1, 0))).unwrap().dice() == 4);
+
+        let placed = place_reinforcements(
+            &grid, player1, None, 3, 5, &Placement::TopDown, &mut |_| 0,
+        );
+        assert!(placed.fetch(Cube::from((0, 0))).unwrap().dice() == 4);
+        assert!(placed.fetch(Cube::from((1, 0))).unwrap().dice() == 1);
+    }
+
+    #[test]
+    fn random_placement_uses_pick() {
+        let player1 = Player::new(1, 'A');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(player1, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+
+        let placed = place_reinforcements(
+            &grid, player1, None, 2, 5, &Placement::Random, &mut |n| n - 1,
+        );
+        assert!(placed.fetch(Cube::from((0, 0))).unwrap().dice() == 1);
+        assert!(placed.fetch(Cube::from((1, 0))).unwrap().dice() == 3);
+    }
+
+    #[test]
+    fn manual_placement_phase() {
+        let player2 = Player::new(2, 'B');
+        let board = super::super::canned_2x2_start01().with_reinforcing(2);
+        let ruleset = *Ruleset::new().set_placement(Placement::Manual);
+
+        let choices = choices_from_board(&board, &ruleset);
+        assert!(choices.len() == 1);
+        assert!(*choices[0].action() == Action::Reinforce(Cube::from((0, 0))));
+
+        let board = match choices[0].consequence() {
+            Consequence::Continue(board) => board.to_owned(),
+            _ => panic!("Invalid consequence."),
+        };
+        assert!(*board.reinforcing() == 1);
+
+        let choices = choices_from_board(&board, &ruleset);
+        match choices[0].consequence() {
+            Consequence::TurnOver(board) => {
+                assert!(board.players().current() == player2);
+                assert!(board.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 4);
+            },
+            _ => panic!("Invalid consequence."),
+        }
+    }
+
+    #[test]
+    fn reserve_banked_and_drawn() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let players = Players::new(2);
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 4, true)),
+            ((1, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let mut ruleset = Ruleset::new();
+        ruleset.set_reinforcement(Reinforcement::Captured).set_max_reserve(2);
+
+        // Four dice captured. One fits, two are banked and the last is over the cap.
+        let board = Board::new(players, grid.clone(), 4, 1);
+        let passed = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(passed.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 5);
+        assert!(passed.reserve(player1) == 2);
+        assert!(passed.reserve(player2) == 0);
+
+        // With room again the reserve is drawn down.
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(players, grid, 0, 1).with_reserve(player1, 2);
+        let passed = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(passed.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 3);
+        assert!(passed.reserve(player1) == 0);
+    }
+
+    #[test]
+    fn holding_dice_limits() {
+        let player1 = Player::new(1, 'A');
+        let player8 = Player::new(8, 'H');
+        let mut ruleset = Ruleset::new();
+        ruleset.set_reinforcement(Reinforcement::Captured).set_max_dice(10);
+
+        // The `u16` holding fits eight players and ten dice.
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 1, true)),
+            ((1, 0).into(), u16::new(player8, 8, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(8), grid, 9, 1);
+        let passed = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(passed.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 10);
+        assert!(passed.grid().fetch(Cube::from((1, 0))).unwrap().owner() == player8);
+
+        // The `u8` holding is kept to seven dice whatever the ruleset says.
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(Player::new(2, 'B'), 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid, 9, 1);
+        let passed = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(passed.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 7);
+    }
+
+    #[test]
+    fn capital_captured() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 3, true)),
+            ((1, 0).into(), u8::new(player2, 1, true)),
+            ((2, 0).into(), u8::new(player2, 4, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(3, 1), 0, 0)
+            .with_capital(player2, Some((1, 0).into()));
+        assert!(format!("{}", board).contains("B@1"));
+
+        let choices = choices_from_board(&board, &Ruleset::new());
+        let taken = choices[0].consequence().board();
+        assert!(taken.capital(player2).is_none());
+        assert!(taken.grid().iter().all(|ht| ht.data().owner() == player1));
+
+        // The capital holder is knocked out there and then. Failed attacks change nothing.
+        assert!(taken.players().eliminations() == vec![(player2, 1)]);
+        let failed = choices[0].chance().unwrap().failure().board();
+        assert!(failed.capital(player2) == Some((1, 0).into()));
+
+        let ruleset = *Ruleset::new().set_capital_loss(CapitalLoss::Neutral);
+        let choices = choices_from_board(&board, &ruleset);
+        let taken = choices[0].consequence().board();
+        assert!(taken.grid().fetch(Cube::from((2, 0))).unwrap().owner().is_neutral());
+        assert!(taken.grid().fetch(Cube::from((1, 0))).unwrap().owner() == player1);
+    }
+
+    #[test]
+    fn neutral_hexes_ignored() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let neutral = Player::neutral();
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(neutral, 5, true)),
+            ((2, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(3, 1), 0, 0);
+
+        // Neutral dice never attack so nobody can do anything.
+        assert!(stalemate(&board, &AttackRule::default()));
+        assert!(!winner(&board));
+
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 3, true)),
+            ((1, 0).into(), u8::new(neutral, 2, true)),
+            ((2, 0).into(), u8::new(neutral, 5, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(3, 1), 0, 0);
+        let attacks = all_legal_attacks_from(
+            board.grid(), board.players(), &AttackRule::default(),
+        );
+        assert!(attacks.len() == 1);
+        assert!(winner(&board));
+        assert!(loser(&board.clone().with_players(board.players().next())));
+    }
+
+    #[test]
+    fn attack_odds() {
+        let attack = |ad, dd| Action::Attack((0, 0).into(), (1, 0).into(), ad, dd);
+        let defender_wins = Ruleset::new();
+        let attacker_wins = *Ruleset::new().set_tie(TieRule::AttackerWins);
+        let grid = canned_2x1_start01().grid().to_owned();
+
+        let odds = win_probability(&grid, attack(1, 1), &defender_wins);
+        assert!((odds - 15_f64 / 36_f64).abs() < 1e-9);
+        let odds = win_probability(&grid, attack(1, 1), &attacker_wins);
+        assert!((odds - 21_f64 / 36_f64).abs() < 1e-9);
+        assert!(win_probability(&grid, attack(2, 1), &defender_wins) > 0.8_f64);
+        assert!(win_probability(&grid, attack(1, 7), &defender_wins) == 0_f64);
+        assert!(win_probability(&grid, Action::Pass, &defender_wins) == 0_f64);
+    }
+
+    #[test]
+    fn terrain_in_combat() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 3, true)),
+            ((1, 0).into(), u16::new(player2, 2, true)),
+            ((0, 1).into(), u16::new(player2, 1, true)),
+            ((1, 1).into(), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0);
+        let ruleset = Ruleset::new();
+        let on = |board: &Board<u16>, cube: (i32, i32)| {
+            all_legal_attacks_from(board.grid(), board.players(), ruleset.attack())
+                .iter()
+                .any(|attack| match attack {
+                    Action::Attack(_, to, _, _) => *to == Cube::from(cube),
+                    _ => false,
+                })
+        };
+        assert!(on(&board, (1, 0)) && on(&board, (0, 1)));
+
+        // Water can't be attacked and mountains defend with an extra die.
+        let board = board
+            .with_terrain((0, 1).into(), Terrain::Water)
+            .with_terrain((1, 0).into(), Terrain::Mountains);
+        assert!(on(&board, (1, 0)) && !on(&board, (0, 1)));
+
+        let attack = Action::Attack((0, 0).into(), (1, 0).into(), 3, 2);
+        let odds = win_probability(board.grid(), attack, &ruleset);
+        assert!(odds == probability::attack_odds(3, 3, Die::D6, &TieRule::DefenderWins));
+
+        // The conquered hex is still a mountain.
+        let grid = grid_from_move(board.grid(), attack);
+        assert!(grid.fetch(Cube::from((1, 0))).unwrap().terrain() == Terrain::Mountains);
+        assert!(grid.fetch(Cube::from((1, 0))).unwrap().owner() == player1);
+        assert!(format!("{}", board).contains("~~~"));
+    }
+
+    #[test]
+    fn victory_points_from_objectives() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 1, true)),
+            ((1, 0).into(), u16::new(player1, 1, true)),
+            ((0, 1).into(), u16::new(player2, 1, true)),
+            ((1, 1).into(), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_objective((0, 0).into(), true)
+            .with_objective((1, 0).into(), true)
+            .with_objective((1, 1).into(), true);
+        let mut ruleset = Ruleset::new();
+
+        // Points are earned at the end of the turn, one for each objective held.
+        let board = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(board.points(player1) == 2 && board.points(player2) == 0);
+        assert!(format!("{}", board).contains("A*"));
+
+        // Reaching the target wins whoever's turn it is.
+        let choices = choices_from_board(&board, &ruleset);
+        assert!(choices.iter().all(|choice| match choice.consequence() {
+            Consequence::VictoryPoints(_) => false,
+            _ => true,
+        }));
+
+        ruleset.set_victory_points(2);
+        let choices = choices_from_board(&board, &ruleset);
+        assert!(choices.len() == 1);
+        match choices[0].consequence() {
+            Consequence::VictoryPoints(board) => assert!(points_leader(board) == player1),
+            _ => panic!("Expected a win on points"),
+        }
+    }
+
+    #[test]
+    fn hex_capacity() {
+        let player1 = Player::new(1, 'A');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 1, true)),
+            ((1, 0).into(), u16::new(player1, 4, true)),
+            ((0, 1).into(), u16::new(player1, 5, true)),
+            ((1, 1).into(), u16::new(player1, 5, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_capacity((0, 0).into(), Some(8))
+            .with_capacity((1, 0).into(), Some(2))
+            .with_reserve(player1, 20);
+        let dice = |board: &Board<u16>, cube: (i32, i32)| {
+            board.grid().fetch(Cube::from(cube)).unwrap().dice()
+        };
+
+        // The marsh loses the dice it can't hold.
+        assert!(dice(&board, (1, 0)) == 2);
+        assert!(room(board.grid(), player1, 5) == 7);
+
+        // Fortresses fill past the ruleset's `max_dice` and marshes stop short of it.
+        let ruleset = Ruleset::new();
+        for placement in &[Placement::TopDown, Placement::BorderFirst, Placement::Random] {
+            let grid = place_reinforcements(
+                board.grid(), player1, None, 20, 5, placement, &mut |_| 0,
+            );
+            assert!(grid.fetch(Cube::from((0, 0))).unwrap().dice() == 8);
+            assert!(grid.fetch(Cube::from((1, 0))).unwrap().dice() == 2);
+        }
+        let board = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(dice(&board, (0, 0)) == 8 && dice(&board, (1, 0)) == 2);
+
+        // Generated maps keep each hex's capacity.
+        let mut rng = rngs::StdRng::seed_from_u64(1234);
+        for _ in 0..10 {
+            let grid = populate_handicapped_grid(
+                board.grid(), Players::new(2), 0_f64, &ruleset, &mut rng,
+            );
+            let marsh = grid.fetch(Cube::from((1, 0))).unwrap();
+            assert!(marsh.capacity() == Some(2) && marsh.dice() <= 2);
+        }
+    }
+
+    #[test]
+    fn water_dealt_to_nobody() {
+        let blank = u16::new(Player::neutral(), 0, true);
+        let grid: Grid<u16> = Rectangular::generate(3, 3, blank).into();
+        let grid = grid.fork_with(|cube, hold| {
+            if cube.x() == 0 {
+                hold.with_terrain(Terrain::Water)
+            } else {
+                hold
+            }
+        });
+        assert!(grid.iter().any(|ht| !ht.data().terrain().passable()));
+
+        let mut rng = rngs::StdRng::seed_from_u64(1234);
+        let grid = populate_handicapped_grid(
+            &grid, Players::new(2), 0_f64, &Ruleset::new(), &mut rng,
+        );
+        assert!(grid.iter().all(|ht| {
+            let hold = ht.data();
+            hold.terrain().passable() != (hold.owner().is_neutral() && hold.dice() == 0)
+        }));
+    }
+
+    #[test]
+    fn attack_into_marsh() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 6, true)),
+            ((1, 0).into(), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 1), 0, 0)
+            .with_capacity((1, 0).into(), Some(2));
+
+        // Only as many dice as the marsh can hold move in. The rest stay behind.
+        let attack = Action::Attack((0, 0).into(), (1, 0).into(), 6, 1);
+        let grid = grid_from_move(board.grid(), attack);
+        assert!(grid.fetch(Cube::from((0, 0))).unwrap().dice() == 4);
+        assert!(grid.fetch(Cube::from((1, 0))).unwrap().dice() == 2);
+        assert!(grid.fetch(Cube::from((1, 0))).unwrap().owner() == player1);
+    }
+
+    #[test]
+    fn supply_lines() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(player1, 1, true)),
+            ((2, 0).into(), u8::new(player2, 1, true)),
+            ((3, 0).into(), u8::new(player1, 1, false)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(4, 1), 0, 0)
+            .with_reserve(player1, 10);
+        let mut ruleset = Ruleset::new();
+        ruleset.set_placement(Placement::SupplyLines).set_max_reserve(10);
+        let dice = |board: &Board, cube: (i32, i32)| {
+            board.grid().fetch(Cube::from(cube)).unwrap().dice()
+        };
+
+        // The pocket at the end is cut off so it starves and the rest is banked.
+        let supplied = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(dice(&supplied, (0, 0)) == 5 && dice(&supplied, (1, 0)) == 5);
+        assert!(dice(&supplied, (3, 0)) == 1);
+        assert!(supplied.grid().fetch(Cube::from((3, 0))).unwrap().mobile());
+        assert!(supplied.reserve(player1) == 2);
+
+        // Unless it holds the capital.
+        let board = board.with_capital(player1, Some((3, 0).into()));
+        let supplied = board_from_pass(&board, &ruleset, &mut |_| 0);
+        assert!(dice(&supplied, (3, 0)) == 3);
+        assert!(supplied.reserve(player1) == 0);
+    }
+
+    #[test]
+    fn stalemate_tie_breakers() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 1, true)),
+            ((1, 0).into(), u8::new(player1, 1, true)),
+            ((0, 1).into(), u8::new(player2, 5, true)),
+            ((1, 1).into(), u8::new(player1, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid, 0, 0);
+
+        let ranking = rank_players(&board, &TieBreaker::MostHexes);
+        assert!(ranking.placings() == &vec![(player1, 1), (player2, 2)]);
+
+        let ranking = rank_players(&board, &TieBreaker::MostDice);
+        assert!(ranking.winner() == Some(player2));
+    }
+
+    #[test]
+    fn transfers_limited() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 4, true)),
+            ((1, 0).into(), u8::new(player1, 3, true)),
+            ((2, 0).into(), u8::new(player2, 5, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid, 0, 0);
+        let is_transfer = |choice: &&Choice| match choice.action() {
+            Action::Transfer(_, _, _) => true,
+            _ => false,
+        };
+
+        // Disabled by default.
+        let choices = choices_from_board(&board, &Ruleset::new());
+        assert!(choices.iter().filter(is_transfer).count() == 0);
+
+        // Two dice fit into (1, 0). Three can be moved out of it but only one fits.
+        let ruleset = *Ruleset::new().set_transfer_limit(1);
+        let choices = choices_from_board(&board, &ruleset);
+        let transfers: Vec<&Choice> = choices.iter().filter(is_transfer).collect();
+        assert!(transfers.len() == 3);
+
+        let transfer = Action::Transfer((0, 0).into(), (1, 0).into(), 2);
+        let moved = transfers
+            .iter()
+            .find(|choice| *choice.action() == transfer)
+            .unwrap()
+            .consequence()
+            .board()
+            .to_owned();
+        assert!(moved.grid().fetch(Cube::from((0, 0))).unwrap().dice() == 2);
+        assert!(moved.grid().fetch(Cube::from((1, 0))).unwrap().dice() == 5);
+        assert!(*moved.transferred() == 1);
+
+        // The limit has been used up.
+        let choices = choices_from_board(&moved, &ruleset);
+        assert!(choices.iter().filter(is_transfer).count() == 0);
+    }
+
+    #[test]
+    fn team_mates_left_alone() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let players = Players::new(3).with_team(player1, 1).with_team(player3, 1);
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 3, true)),
+            ((1, 0).into(), u8::new(player3, 1, true)),
+            ((2, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+
+        let attacks = all_legal_attacks_from(&grid, &players, &AttackRule::default());
+        assert!(attacks.is_empty());
+
+        // With 'B' gone, 'A' and 'C' share the board between them and win.
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 3, true)),
+            ((1, 0).into(), u8::new(player3, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(players, grid, 0, 0);
+        assert!(winner(&board));
+    }
+
     #[test]
     fn test_turn_over() {
         let player2 = Player::new(2, 'B');
         let board = super::super::canned_2x2_start01();
-        let mut choices = choices_from_board_only_pass_at_end(&board, 6);
+        let mut choices = choices_from_board_only_pass_at_end(&board, &Ruleset::new());
 
         assert!(choices.len() == 1);
         let choice = choices.pop().unwrap();
@@ -376,9 +1565,68 @@ mod test {
         }
     }
 
+    #[test]
+    fn pass_anytime_with_attacks() {
+        let player2 = Player::new(2, 'B');
+        let board = super::super::canned_2x2_start03();
+        let ruleset = *Ruleset::new().set_pass(PassRule::Anytime);
+        let mut choices = choices_from_board(&board, &ruleset);
+
+        assert!(choices.len() == 3);
+        let choice = choices.pop().unwrap();
+        assert!(*choice.action() == Action::Pass);
+        match choice.consequence() {
+            Consequence::TurnOver(board) => {
+                assert!(board.players().current() == player2);
+            },
+            _ => panic!("Invalid consequence."),
+        }
+    }
+
+    #[test]
+    fn pass_anytime_no_double_pass() {
+        let board = super::super::canned_2x2_start01();
+        let ruleset = *Ruleset::new().set_pass(PassRule::Anytime);
+        let choices = choices_from_board(&board, &ruleset);
+
+        assert!(choices.len() == 1);
+        assert!(*choices[0].action() == Action::Pass);
+    }
+
+    #[test]
+    fn failed_attack_penalties() {
+        let board = super::super::canned_2x2_start03();
+        let from = Cube::from((0, 0));
+        let attack = Action::Attack(from, Cube::from((1, 0)), 4, 3);
+
+        let frozen = board_from_failed_attack(&board, attack, FailurePenalty::Freeze);
+        let hold = frozen.grid().fetch(from).unwrap();
+        assert!(hold.dice() == 4);
+        assert!(!hold.mobile());
+        assert!(*frozen.moved() == 1);
+
+        let one = board_from_failed_attack(&board, attack, FailurePenalty::OneDie);
+        let hold = one.grid().fetch(from).unwrap();
+        assert!(hold.dice() == 1);
+        assert!(!hold.mobile());
+
+        let lose = board_from_failed_attack(
+            &board, attack, FailurePenalty::LoseDefenderDice,
+        );
+        let hold = lose.grid().fetch(from).unwrap();
+        assert!(hold.dice() == 1);
+
+        let attack = Action::Attack(from, Cube::from((1, 0)), 4, 2);
+        let lose = board_from_failed_attack(
+            &board, attack, FailurePenalty::LoseDefenderDice,
+        );
+        let hold = lose.grid().fetch(from).unwrap();
+        assert!(hold.dice() == 2);
+    }
+
     #[test]
     fn no_stalemate01() {
-        assert!(!stalemate(&canned_1x1_start()));
+        assert!(!stalemate(&canned_1x1_start(), &AttackRule::default()));
     }
 
     #[test]
@@ -396,7 +1644,7 @@ mod test {
         let board = Board::new(players, grid, 0, 0);
 
         // Test
-        assert!(!stalemate(&board));
+        assert!(!stalemate(&board, &AttackRule::default()));
     }
 
     #[test]
@@ -414,7 +1662,7 @@ mod test {
         let board = Board::new(players, grid, 0, 0);
 
         // Test
-        assert!(!stalemate(&board));
+        assert!(!stalemate(&board, &AttackRule::default()));
     }
 
     #[test]
@@ -434,7 +1682,7 @@ mod test {
         let board = Board::new(players, grid, 0, 0);
 
         // Test
-        assert!(!stalemate(&board));
+        assert!(!stalemate(&board, &AttackRule::default()));
     }
 
     /// This is testing `canned_3x3_start02` and is related to `stalemate04` as that's
@@ -445,7 +1693,7 @@ mod test {
         let board = crate::game::canned_3x3_start02();
 
         // Test
-        assert!(!stalemate(&board));
+        assert!(!stalemate(&board, &AttackRule::default()));
     }
 
     #[test]
@@ -463,7 +1711,7 @@ mod test {
         let board = Board::new(players, grid, 0, 0);
 
         // Test
-        assert!(stalemate(&board));
+        assert!(stalemate(&board, &AttackRule::default()));
     }
 
     #[test]
@@ -483,7 +1731,7 @@ mod test {
         let board = Board::new(players, grid, 0, 0);
 
         // Test
-        assert!(stalemate(&board));
+        assert!(stalemate(&board, &AttackRule::default()));
     }
 
     /// The result taking one of the two possible moves from `canned_3x3_start02`. Either
@@ -509,7 +1757,7 @@ mod test {
         let board = Board::new(players, grid.change_to_rectangle(3, 3), 0, 0);
 
         // Test
-        assert!(stalemate(&board));
+        assert!(stalemate(&board, &AttackRule::default()));
     }
 
     /// The the result of taking both possible moves from `canned_3x1_start01`.
@@ -529,6 +1777,6 @@ mod test {
         let board = Board::new(players, grid.change_to_rectangle(3, 1), 0, 0);
 
         // Test
-        assert!(stalemate(&board));
+        assert!(stalemate(&board, &AttackRule::default()));
     }
 }
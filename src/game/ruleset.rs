@@ -0,0 +1,471 @@
+//! Configurable game rules. A `Ruleset` is handed to the tree generation and `Session` so
+//! that house rules can be played without forking the crate.
+use std::num::NonZeroU8;
+
+use derive_getters::Getters;
+
+use super::model::Holding;
+use super::player::{Player, MAX_PLAYERS};
+
+/// Most dice any holding can encode. That's the `u16` holding.
+pub (in crate::game) const HOLDING_DICE_LIMIT: u8 = <u16 as Holding>::DICE_LIMIT;
+
+/// When the `Action::Pass` choice is offered to a player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassRule {
+    /// Only once there are no attacks left or the move limit has been exceeded. Keeps the
+    /// tree small.
+    OnlyAtEnd,
+
+    /// Alongside the attacks on every move. Lets a player stop early and bank their
+    /// reinforcements at the cost of a much larger tree.
+    Anytime,
+}
+
+impl Default for PassRule {
+    fn default() -> Self {
+        PassRule::OnlyAtEnd
+    }
+}
+
+/// What happens to the attacking hex when an attack fails. The hex is always frozen for
+/// the rest of the turn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FailurePenalty {
+    /// The attacking hex keeps all its dice.
+    Freeze,
+
+    /// The attacking hex is reduced down to one die. Classic Dice Wars.
+    OneDie,
+
+    /// The attacking hex loses as many dice as the defender had, keeping at least one.
+    LoseDefenderDice,
+}
+
+impl Default for FailurePenalty {
+    fn default() -> Self {
+        FailurePenalty::Freeze
+    }
+}
+
+/// Which hexes may be attacked. An attacking hex always needs more than one die.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AttackRule {
+    /// The defender may have as many dice as the attacker but no more.
+    NotOutnumbered,
+
+    /// The attacker must have strictly more dice than the defender.
+    Outnumbering,
+
+    /// Any neighbouring enemy hex can be attacked.
+    Anything,
+}
+
+impl AttackRule {
+    pub fn allows(&self, attacker_dice: u8, defender_dice: u8) -> bool {
+        if attacker_dice < 2 {
+            return false;
+        }
+
+        match self {
+            AttackRule::NotOutnumbered => defender_dice <= attacker_dice,
+            AttackRule::Outnumbering => defender_dice < attacker_dice,
+            AttackRule::Anything => true,
+        }
+    }
+}
+
+impl Default for AttackRule {
+    fn default() -> Self {
+        AttackRule::NotOutnumbered
+    }
+}
+
+/// How many dice a player gets at the end of their turn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Reinforcement {
+    /// The amount of dice captured during the turn minus one.
+    CapturedMinusOne,
+
+    /// The amount of dice captured during the turn.
+    Captured,
+
+    /// One die per hex in the player's largest connected region. Classic Dice Wars.
+    LargestRegion,
+}
+
+impl Default for Reinforcement {
+    fn default() -> Self {
+        Reinforcement::CapturedMinusOne
+    }
+}
+
+/// Where reinforcements are placed on the player's hexes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Placement {
+    /// From the top leftmost of the player's hexes downwards. Predictable.
+    TopDown,
+
+    /// Hexes bordering an enemy are filled up first, then the rest top down.
+    BorderFirst,
+
+    /// One die at a time onto a random hex with room left. The game tree can't know the
+    /// rolls so it models this as always picking the first hex with room. `Session` does
+    /// the actual placement with its `DiceRoller`.
+    Random,
+
+    /// The player picks each hex with `Action::Reinforce` after passing.
+    Manual,
+
+    /// Top down but only onto hexes connected to the player's largest region or their
+    /// capital. Cut off pockets get nothing.
+    SupplyLines,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement::TopDown
+    }
+}
+
+/// How the players are ranked when the game ends in a stalemate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TieBreaker {
+    MostHexes,
+    MostDice,
+
+    /// Size of the player's largest group of connected hexes.
+    LargestRegion,
+}
+
+impl Default for TieBreaker {
+    fn default() -> Self {
+        TieBreaker::MostHexes
+    }
+}
+
+/// What becomes of a player's hexes once their capital is captured.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CapitalLoss {
+    /// The captor takes them all.
+    ToCaptor,
+
+    /// They all turn neutral keeping their dice.
+    Neutral,
+}
+
+impl Default for CapitalLoss {
+    fn default() -> Self {
+        CapitalLoss::ToCaptor
+    }
+}
+
+/// What becomes of a player's hexes when they resign.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Resignation {
+    /// They all turn neutral keeping their dice.
+    Neutral,
+
+    /// They stay with the resigned player, frozen for good, until they're conquered.
+    Frozen,
+}
+
+impl Default for Resignation {
+    fn default() -> Self {
+        Resignation::Neutral
+    }
+}
+
+/// The kind of dice rolled in battle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Die {
+    D4,
+    D6,
+    D8,
+    D10,
+    D20,
+}
+
+impl Die {
+    pub fn faces(&self) -> u8 {
+        match self {
+            Die::D4 => 4,
+            Die::D6 => 6,
+            Die::D8 => 8,
+            Die::D10 => 10,
+            Die::D20 => 20,
+        }
+    }
+}
+
+impl Default for Die {
+    fn default() -> Self {
+        Die::D6
+    }
+}
+
+/// Who wins when both sides roll the same.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TieRule {
+    DefenderWins,
+    AttackerWins,
+
+    /// Both sides roll again until someone rolls higher.
+    Reroll,
+}
+
+impl TieRule {
+    /// Whether the attacker won the rolls. With `TieRule::Reroll` a tie isn't a win and
+    /// has to be checked for first.
+    pub fn attacker_wins(&self, attacker_rolled: usize, defender_rolled: usize) -> bool {
+        match self {
+            TieRule::DefenderWins | TieRule::Reroll => attacker_rolled > defender_rolled,
+            TieRule::AttackerWins => attacker_rolled >= defender_rolled,
+        }
+    }
+}
+
+impl Default for TieRule {
+    fn default() -> Self {
+        TieRule::DefenderWins
+    }
+}
+
+/// Evens out the odds for a weaker player, or an easier AI, against stronger ones. The
+/// default is no handicap at all.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Getters)]
+pub struct Handicap {
+    /// Extra shares of the starting hexes. Every player has one share to begin with so a
+    /// player with one extra share starts with about twice as many hexes as the others.
+    hexes: u8,
+
+    /// Extra dice spread over the player's starting hexes.
+    dice: u8,
+
+    /// Percentage of their usual reinforcements the player gets. 100 is no change.
+    reinforcement: u16,
+}
+
+impl Handicap {
+    pub fn new(hexes: u8, dice: u8, reinforcement: u16) -> Self {
+        Handicap { hexes, dice, reinforcement }
+    }
+
+    /// Scale the `amount` of reinforcements by the handicap's percentage.
+    pub fn reinforce(&self, amount: u8) -> u8 {
+        let scaled = amount as u32 * self.reinforcement as u32 / 100;
+        scaled.min(u8::max_value() as u32) as u8
+    }
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Handicap::new(0, 0, 100)
+    }
+}
+
+/// All the configurable rules of the game. Defaults to the rules the game has always been
+/// played with. Build one up with the setters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Getters)]
+pub struct Ruleset {
+    /// Maximum amount of dice a hexagon holding may have.
+    max_dice: u8,
+
+    /// Most attacks a player can make in one turn.
+    move_limit: NonZeroU8,
+
+    /// Most times a player can move dice between their own hexes in one turn.
+    transfer_limit: u8,
+
+    /// Most reinforcements a player can bank when all their hexes are full. Anything over
+    /// is lost.
+    max_reserve: u8,
+
+    /// Victory points from objectives a player needs to win. Zero disables winning on
+    /// points.
+    victory_points: u8,
+
+    attack: AttackRule,
+    die: Die,
+    reinforcement: Reinforcement,
+    placement: Placement,
+    tie: TieRule,
+    tie_breaker: TieBreaker,
+    capital_loss: CapitalLoss,
+    resignation: Resignation,
+    pass: PassRule,
+    failure_penalty: FailurePenalty,
+
+    /// Handicap of each player. Indexed by player number less one.
+    handicaps: [Handicap; MAX_PLAYERS],
+}
+
+impl Ruleset {
+    pub fn new() -> Self {
+        Ruleset {
+            max_dice: 5,
+            move_limit: NonZeroU8::new(6).unwrap(),
+            transfer_limit: 0,
+            max_reserve: 0,
+            victory_points: 0,
+            attack: AttackRule::default(),
+            die: Die::default(),
+            reinforcement: Reinforcement::default(),
+            placement: Placement::default(),
+            tie: TieRule::default(),
+            tie_breaker: TieBreaker::default(),
+            capital_loss: CapitalLoss::default(),
+            resignation: Resignation::default(),
+            pass: PassRule::default(),
+            failure_penalty: FailurePenalty::default(),
+            handicaps: [Handicap::default(); MAX_PLAYERS],
+        }
+    }
+
+    /// Clamped between 1 and the most dice any holding can encode. Boards with a narrower
+    /// holding are kept to its own `Holding::DICE_LIMIT` during play.
+    pub fn set_max_dice(&mut self, max_dice: u8) -> &mut Self {
+        self.max_dice = max_dice.clamp(1, HOLDING_DICE_LIMIT);
+        self
+    }
+
+    pub fn set_move_limit(&mut self, move_limit: NonZeroU8) -> &mut Self {
+        self.move_limit = move_limit;
+        self
+    }
+
+    /// Zero, the default, disables `Action::Transfer`.
+    pub fn set_transfer_limit(&mut self, transfer_limit: u8) -> &mut Self {
+        self.transfer_limit = transfer_limit;
+        self
+    }
+
+    pub fn set_max_reserve(&mut self, max_reserve: u8) -> &mut Self {
+        self.max_reserve = max_reserve;
+        self
+    }
+
+    /// Zero, the default, means objectives are tallied but never win the game.
+    pub fn set_victory_points(&mut self, victory_points: u8) -> &mut Self {
+        self.victory_points = victory_points;
+        self
+    }
+
+    pub fn set_attack(&mut self, attack: AttackRule) -> &mut Self {
+        self.attack = attack;
+        self
+    }
+
+    pub fn set_die(&mut self, die: Die) -> &mut Self {
+        self.die = die;
+        self
+    }
+
+    pub fn set_reinforcement(&mut self, reinforcement: Reinforcement) -> &mut Self {
+        self.reinforcement = reinforcement;
+        self
+    }
+
+    pub fn set_placement(&mut self, placement: Placement) -> &mut Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn set_tie(&mut self, tie: TieRule) -> &mut Self {
+        self.tie = tie;
+        self
+    }
+
+    pub fn set_tie_breaker(&mut self, tie_breaker: TieBreaker) -> &mut Self {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    pub fn set_capital_loss(&mut self, capital_loss: CapitalLoss) -> &mut Self {
+        self.capital_loss = capital_loss;
+        self
+    }
+
+    pub fn set_resignation(&mut self, resignation: Resignation) -> &mut Self {
+        self.resignation = resignation;
+        self
+    }
+
+    /// `PassRule::Anytime` lets players stop attacking early to bank their reinforcements.
+    pub fn set_pass(&mut self, pass: PassRule) -> &mut Self {
+        self.pass = pass;
+        self
+    }
+
+    pub fn set_failure_penalty(&mut self, failure_penalty: FailurePenalty) -> &mut Self {
+        self.failure_penalty = failure_penalty;
+        self
+    }
+
+    /// Handicap `player`. Has no effect on the neutral player.
+    pub fn set_handicap(&mut self, player: Player, handicap: Handicap) -> &mut Self {
+        if let Some(slot) = self.handicaps.get_mut(player.number().wrapping_sub(1)) {
+            *slot = handicap;
+        }
+        self
+    }
+
+    /// The handicap of `player`. No handicap for the neutral player.
+    pub fn handicap(&self, player: Player) -> Handicap {
+        self.handicaps
+            .get(player.number().wrapping_sub(1))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn attack_rules() {
+        assert!(AttackRule::NotOutnumbered.allows(2, 2));
+        assert!(!AttackRule::NotOutnumbered.allows(2, 3));
+        assert!(!AttackRule::Outnumbering.allows(2, 2));
+        assert!(AttackRule::Outnumbering.allows(3, 2));
+        assert!(AttackRule::Anything.allows(2, 5));
+        assert!(!AttackRule::Anything.allows(1, 1));
+    }
+
+    #[test]
+    fn tie_rules() {
+        assert!(!TieRule::DefenderWins.attacker_wins(7, 7));
+        assert!(TieRule::AttackerWins.attacker_wins(7, 7));
+        assert!(!TieRule::AttackerWins.attacker_wins(6, 7));
+        assert!(!TieRule::Reroll.attacker_wins(7, 7));
+    }
+
+    #[test]
+    fn handicaps() {
+        let player1 = Player::new(1, 'A');
+        let mut ruleset = Ruleset::new();
+
+        assert!(ruleset.handicap(player1) == Handicap::default());
+        assert!(Handicap::default().reinforce(7) == 7);
+
+        ruleset.set_handicap(player1, Handicap::new(1, 3, 150));
+        assert!(ruleset.handicap(player1).reinforce(5) == 7);
+        assert!(ruleset.handicap(Player::new(2, 'B')) == Handicap::default());
+        assert!(ruleset.handicap(Player::neutral()) == Handicap::default());
+    }
+
+    #[test]
+    fn max_dice_clamped() {
+        let mut ruleset = Ruleset::new();
+
+        assert!(*ruleset.set_max_dice(0).max_dice() == 1);
+        assert!(*ruleset.set_max_dice(200).max_dice() == HOLDING_DICE_LIMIT);
+    }
+}
@@ -1,21 +1,41 @@
 //! Primitive AI that works on scoring moves in advance and chooses the highest scoring one
 //! during play.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
-use super::{Board, Player, Tree, Consequence, Score, Holding};
+use super::{Board, Player, Tree, Consequence, Score, Holding, Chance};
+use super::rules::points_leader;
+
+/// How many ordinary hexes an objective hex is worth when scoring a board.
+const OBJECTIVE_WEIGHT: usize = 3;
+
+/// Scores of the boards already worked out during one pass over the tree. A board can be
+/// reached by more than one choice, such as the failure board shared by every attack from
+/// the same hex, and must score the same each time.
+struct Memo<H: Holding> {
+    scored: HashMap<Board<H>, HashMap<Player, Score>>,
+
+    /// Boards still being worked out. Failed attacks can lead back to one of them.
+    pending: HashSet<Board<H>>,
+}
+
+impl<H: Holding> Memo<H> {
+    fn new() -> Self {
+        Memo { scored: HashMap::new(), pending: HashSet::new() }
+    }
+}
 
 /// Wipe all scoring from the tree.
-pub fn clear_all_scoring(tree: &Tree) {
+pub fn clear_all_scoring<H: Holding>(tree: &Tree<H>) {
     clear(tree.root(), tree);
 }
 
 /// Like above but only starting from the specified board.
-pub fn clear_scoring_from(from: &Board, tree: &Tree) {
+pub fn clear_scoring_from<H: Holding>(from: &Board<H>, tree: &Tree<H>) {
     clear(from, tree);
 }
 
-fn clear(board: &Board, tree: &Tree) {
+fn clear<H: Holding>(board: &Board<H>, tree: &Tree<H>) {
     let choices = match tree.fetch_choices(board) {
         Some(choices) => choices,
         None => return,
@@ -34,19 +54,22 @@ fn clear(board: &Board, tree: &Tree) {
             },
             _ => (),
         }
+        if let Some(chance) = choice.chance() {
+            clear(chance.failure().board(), tree);
+        }
         choice.clear_score();
     }
 }
 
 /// Score all the nodes moves in the tree. Return the number of moves scored.
-pub fn score_tree(tree: &Tree) -> usize {
-    let (touched, _) = score(tree.root(), tree);
+pub fn score_tree<H: Holding>(tree: &Tree<H>) -> usize {
+    let (touched, _) = score(tree.root(), tree, &mut Memo::new());
     touched
 }
 
 /// Score a section of the tree starting from the supplied `Board`.
-pub fn score_tree_from(from: &Board, tree: &Tree) -> usize {
-    let (touched, _) = score(from, tree);
+pub fn score_tree_from<H: Holding>(from: &Board<H>, tree: &Tree<H>) -> usize {
+    let (touched, _) = score(from, tree, &mut Memo::new());
     touched
 }
 
@@ -54,18 +77,27 @@ pub fn score_tree_from(from: &Board, tree: &Tree) -> usize {
 /// that the board has already been checked to not be a winning or losing board.
 ///
 /// This will create a score by calculating the percentage of occupied tiles. No further
-/// analysis is done.
-fn score_board(board: &Board) -> HashMap<Player, Score> {
+/// analysis is done. Neutral hexes count towards the tiles but aren't scored. Water isn't
+/// counted at all. Objective hexes count as `OBJECTIVE_WEIGHT` tiles.
+fn score_board<H: Holding>(board: &Board<H>) -> HashMap<Player, Score> {
     let mut count: HashMap<Player, usize> = HashMap::new();
-    let tiles = board.grid().len() as f64;
+    let weight = |hold: &H| if hold.objective() { OBJECTIVE_WEIGHT } else { 1 };
+    let tiles = board
+        .grid()
+        .iter()
+        .filter(|ht| ht.data().terrain().passable())
+        .map(|ht| weight(ht.data()))
+        .sum::<usize>() as f64;
     
     board
         .grid()
         .iter()
+        .filter(|ht| ht.data().terrain().passable() && !ht.data().owner().is_neutral())
         .for_each(|ht| {
+            let held = weight(ht.data());
             count.entry(ht.data().owner())
-                .and_modify(|c| *c += 1)
-                .or_insert(1);
+                .and_modify(|c| *c += held)
+                .or_insert(held);
         });
 
     count
@@ -77,7 +109,32 @@ fn score_board(board: &Board) -> HashMap<Player, Score> {
         .collect()
 }
 
-fn score(board: &Board, tree: &Tree) -> (usize, HashMap<Player, Score>) {    
+/// Score the `board` reusing the scores of any board already worked out in this pass.
+/// `None` if every way on from the `board` cycles back to a board still being worked out.
+fn score<H: Holding>(
+    board: &Board<H>, tree: &Tree<H>, memo: &mut Memo<H>,
+) -> (usize, Option<HashMap<Player, Score>>) {
+    if let Some(scores) = memo.scored.get(board) {
+        return (0, Some(scores.clone()));
+    }
+
+    // A cycle back to a board still being worked out is only followed once. It has no
+    // score of its own so it's left out rather than counted as a loss.
+    if !memo.pending.insert(board.clone()) {
+        return (0, None);
+    }
+
+    let (visited, scores) = score_choices(board, tree, memo);
+    memo.pending.remove(board);
+    if let Some(ref scores) = scores {
+        memo.scored.insert(board.clone(), scores.clone());
+    }
+    (visited, scores)
+}
+
+fn score_choices<H: Holding>(
+    board: &Board<H>, tree: &Tree<H>, memo: &mut Memo<H>,
+) -> (usize, Option<HashMap<Player, Score>>) {    
     let mut scores: HashMap<Player, Score> = HashMap::new();
     let player = board.players().current();
     let choices = match tree.fetch_choices(board) {
@@ -85,25 +142,18 @@ fn score(board: &Board, tree: &Tree) -> (usize, HashMap<Player, Score>) {
         None => {
             // The tree has been partially calculated and we've reached the end. Score the
             // board as it stands and return it.
-            return (0, score_board(board))
+            return (0, Some(score_board(board)))
         },
     };
     let mut sum = 0;
     for choice in choices {
-        // Since we are using a hashmap as the underlying tree data storage, there's a
-        // chance that cycles can appear. Thus, if this choice already has a score, we
-        // skip it. Otherwise we'll loop unecessarily re-visiting already scored choices.
-        if choice.score().is_some() {
-            continue;
-        }
-        
         let consequence = choice.consequence();
         let (visited, sub_scores) = match consequence {
             Consequence::Stalemate(ref board) => {
                 // Game could end here. It's not an ideal end.
                 let sub_scores = score_board(&board);
                 choice.set_score(*sub_scores.get(&player).unwrap());                
-                return (1, sub_scores);
+                return (1, Some(sub_scores));
             },
             Consequence::Winner(_) => {
                 // Game could end here. Give the best score and return.
@@ -111,18 +161,50 @@ fn score(board: &Board, tree: &Tree) -> (usize, HashMap<Player, Score>) {
                 choice.set_score(win_score);
                 let mut sub_scores: HashMap<Player, Score> = HashMap::with_capacity(1);
                 sub_scores.insert(player, win_score);
-                return (1, sub_scores);
+                return (1, Some(sub_scores));
+            },
+            Consequence::VictoryPoints(ref board) => {
+                // Game ends on points. Whoever is on the leader's side wins outright.
+                let leader = points_leader(board);
+                let sub_scores: HashMap<Player, Score> = board
+                    .players()
+                    .playing()
+                    .into_iter()
+                    .map(|p| {
+                        let won = board.players().allies(p, leader);
+                        (p, Score::new(if won { 1_f64 } else { 0_f64 }, 0))
+                    })
+                    .collect();
+                choice.set_score(sub_scores.get(&player).cloned().unwrap_or_default());
+                return (1, Some(sub_scores));
             },
             Consequence::GameOver(ref board) => {
                 // It is game over for the current player. But the game continues.
                 let game_over_score = Score::new(0_f64, 0);
-                let (v, mut sc) = score(board, tree);
+                let (v, sc) = score(board, tree, memo);
+                let mut sc = match sc {
+                    Some(sc) => sc,
+                    None => {
+                        sum += v;
+                        continue;
+                    },
+                };
                 assert!(sc.insert(player, game_over_score).is_none());
                 choice.set_score(game_over_score);
                 (v, sc)
             },
             Consequence::Continue(ref board) | Consequence::TurnOver(ref board) => {
-                let (v, mut sc) = score(board, tree);
+                let (v, sc) = match choice.chance() {
+                    Some(chance) => score_chance(board, chance, tree, memo),
+                    None => score(board, tree, memo),
+                };
+                let mut sc = match sc {
+                    Some(sc) => sc,
+                    None => {
+                        sum += v;
+                        continue;
+                    },
+                };
                 // A player that has lost may never get the chance to `GameOver` as the
                 // game would end before their next turn. Thus their score is absent
                 // which will cause a crash if this trunk node was their last play.
@@ -159,13 +241,53 @@ fn score(board: &Board, tree: &Tree) -> (usize, HashMap<Player, Score>) {
         sum += visited;
     }    
 
-    (sum + 1, scores)
+    // Nothing to go on if every choice cycled.
+    if scores.is_empty() {
+        return (sum + 1, None);
+    }
+
+    (sum + 1, Some(scores))
+}
+
+/// Score both branches of a roll of the dice. Each player gets the expectation of their
+/// best scores from either branch. A branch that cycles is left out and the other branch
+/// is taken as it is.
+fn score_chance<H: Holding>(
+    success: &Board<H>, chance: &Chance<H>, tree: &Tree<H>, memo: &mut Memo<H>,
+) -> (usize, Option<HashMap<Player, Score>>) {
+    let (s_visited, s_scores) = score(success, tree, memo);
+    let (f_visited, f_scores) = score(chance.failure().board(), tree, memo);
+    let (s_scores, f_scores) = match (s_scores, f_scores) {
+        (Some(s_scores), Some(f_scores)) => (s_scores, f_scores),
+        (s_scores, f_scores) => return (s_visited + f_visited, s_scores.or(f_scores)),
+    };
+
+    let players: Vec<Player> = s_scores
+        .keys()
+        .chain(f_scores.keys())
+        .cloned()
+        .collect();
+
+    let scores = players
+        .into_iter()
+        .map(|player| {
+            let s_score = s_scores.get(&player).cloned().unwrap_or_default();
+            let f_score = f_scores.get(&player).cloned().unwrap_or_default();
+            (player, Score::expected(s_score, f_score, *chance.success()))
+        })
+        .collect();
+
+    (s_visited + f_visited, Some(scores))
 }
 
 #[cfg(test)]
 mod test {
     use crate::game;
-    use super::super::build_tree;
+    use crate::hexagon::{Cube, Grid};
+    use super::super::{
+        build_tree, start_tree_horizon_limited, Players, Ruleset, Terrain, PassRule,
+        FailurePenalty,
+    };
     use super::*;
 
     #[test]
@@ -181,9 +303,96 @@ mod test {
         assert!(*scores.get(&player2).unwrap().destination() == 0.75_f64);
     }
 
+    #[test]
+    fn objectives_weigh_more() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 1, true)),
+            ((1, 0).into(), u16::new(player2, 1, true)),
+            ((0, 1).into(), u16::new(player2, 1, true)),
+            ((1, 1).into(), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_objective((0, 0).into(), true);
+        let scores = score_board(&board);
+
+        assert!(*scores.get(&player1).unwrap().destination() == 0.5_f64);
+        assert!(*scores.get(&player2).unwrap().destination() == 0.5_f64);
+    }
+
+    #[test]
+    fn water_not_scored() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u16)> = vec![
+            ((0, 0).into(), u16::new(player1, 1, true)),
+            ((1, 0).into(), u16::new(player2, 1, true)),
+            ((0, 1).into(), u16::new(player2, 1, true)),
+            ((1, 1).into(), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_terrain((0, 1).into(), Terrain::Water)
+            .with_terrain((1, 1).into(), Terrain::Water);
+        let scores = score_board(&board);
+
+        assert!(*scores.get(&player1).unwrap().destination() == 0.5_f64);
+        assert!(*scores.get(&player2).unwrap().destination() == 0.5_f64);
+    }
+
+    #[test]
+    fn equivalent_attacks_score_the_same() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player2, 1, true)),
+            ((1, 0).into(), u8::new(player1, 5, true)),
+            ((2, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(3, 1), 0, 0);
+
+        // Both attacks fail into the same frozen board.
+        let tree = start_tree_horizon_limited(board.clone(), 3, &Ruleset::new());
+        score_tree(&tree);
+
+        let choices = tree.fetch_choices(&board).unwrap();
+        assert!(choices.len() == 2);
+        let (left, right) = (choices[0].score().unwrap(), choices[1].score().unwrap());
+        assert!(*left.destination() > 0.6_f64);
+        assert!(left == right);
+    }
+
+    #[test]
+    fn passing_around_not_a_loss() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes: Vec<(Cube, u8)> = vec![
+            ((0, 0).into(), u8::new(player1, 2, true)),
+            ((1, 0).into(), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let board = Board::new(Players::new(2), grid.change_to_rectangle(2, 1), 0, 0);
+        let mut ruleset = Ruleset::new();
+        ruleset.set_pass(PassRule::Anytime).set_failure_penalty(FailurePenalty::Freeze);
+
+        // Passing, or failing the attack, only comes back around to the same board.
+        let tree = build_tree(board.clone(), &ruleset);
+        score_tree(&tree);
+
+        let choices = tree.fetch_choices(&board).unwrap();
+        let attack = choices
+            .iter()
+            .find(|choice| choice.chance().is_some())
+            .unwrap();
+        assert!(*attack.score().unwrap().destination() == 1_f64);
+    }
+
     #[test]
     fn insta_win_1x1() {
-        let tree = build_tree(game::canned_1x1_start(), 100);
+        let tree = build_tree(game::canned_1x1_start(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
@@ -195,7 +404,7 @@ mod test {
 
     #[test]
     fn insta_win_2x1() {
-        let tree = build_tree(game::canned_2x1_start03(), 100);
+        let tree = build_tree(game::canned_2x1_start03(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
@@ -207,7 +416,7 @@ mod test {
 
     #[test]
     fn stalemate_2x1() {
-        let tree = build_tree(game::canned_2x1_start02(), 20);
+        let tree = build_tree(game::canned_2x1_start02(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
@@ -219,7 +428,7 @@ mod test {
 
     #[test]
     fn game_2x1() {
-        let tree = build_tree(game::canned_2x1_start01(), 20);
+        let tree = build_tree(game::canned_2x1_start01(), &Ruleset::new());
         score_tree(&tree);
 
         // First move
@@ -227,7 +436,8 @@ mod test {
         assert!(choices.len() == 1);
         assert!(choices[0].score().unwrap() == Score::new(0_f64, 0));
 
-        // Second move
+        // Second move. A failed attack only comes back around to try again so it's won in
+        // the end.
         let next_board = choices[0].consequence().board().to_owned();
         let choices = tree.fetch_choices(&next_board).unwrap();
         assert!(choices.len() == 1);
@@ -242,7 +452,7 @@ mod test {
 
     #[test]
     fn insta_win_3x1() {
-        let tree = build_tree(game::canned_3x1_start02(), 10);
+        let tree = build_tree(game::canned_3x1_start02(), &Ruleset::new());
         score_tree(&tree);
 
         // There are actually two moves as player 'B' is the winner. Player 'A' has to
@@ -262,7 +472,7 @@ mod test {
 
     #[test]
     fn stalemate_3x1() {
-        let tree = build_tree(game::canned_3x1_start03(), 20);
+        let tree = build_tree(game::canned_3x1_start03(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
@@ -275,7 +485,7 @@ mod test {
     /*
     #[test]
     fn game_3x1() {
-        let tree = build_tree(game::canned_3x1_start01(), 100);
+        let tree = build_tree(game::canned_3x1_start01(), &Ruleset::new());
         score_tree(&tree);
 
         // Player 'B' is the eventual winner. But player 'A' needs to pass first.
@@ -330,20 +540,32 @@ mod test {
      */
 
     /// Redo of the test above due to rules changes allowing equal die amounts to fight
-    /// because dice rolling has been introduced. Thus 'A' now wins very quickly.
+    /// because dice rolling has been introduced. Thus 'A' now wins very quickly. Unless
+    /// the dice go against them, which makes it a little worse than a coin toss.
     #[test]
     fn game_3x1() {
-        let tree = build_tree(game::canned_3x1_start01(), 20);
+        let tree = build_tree(game::canned_3x1_start01(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
         assert!(choices.len() == 1);
-        assert!(choices[0].score().unwrap() == Score::new(1_f64, 1));
+        let score = choices[0].score().unwrap();
+        assert!(*score.destination() > 0.4_f64 && *score.destination() < 0.5_f64);
+        assert!(*score.distance() == 3);
+    }
+
+    #[test]
+    fn expected_score() {
+        let success = Score::new(1_f64, 2);
+        let failure = Score::new(0_f64, 4);
+
+        assert!(Score::expected(success, failure, 0.75_f64) == Score::new(0.75_f64, 3));
+        assert!(Score::expected(success, failure, 1_f64) == success);
     }
 
     #[test]
     fn stalemate_3x1_v2() {
-        let tree = build_tree(game::canned_3x1_start04(), 100);
+        let tree = build_tree(game::canned_3x1_start04(), &Ruleset::new());
         score_tree(&tree);
 
         let choices = tree.fetch_choices(tree.root()).unwrap();
@@ -1,7 +1,7 @@
 //! Contain the hexagonal grid using cube coordinates.
 use std::{fmt, mem, iter};
 use std::fmt::Display;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::iter::IntoIterator;
 
@@ -101,6 +101,43 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash> Inner<T> {
             .and_then(|i| Ok(&self.hexes[i].1))
     }
 
+    /// Breadth first flood fill from every unvisited hex. Neighbouring hexes end up in the
+    /// same region when `connected` returns true for both their data.
+    fn regions<F: Fn(&T, &T) -> bool>(&self, connected: F) -> Vec<Vec<Cube>> {
+        let mut visited = vec![false; self.hexes.len()];
+        let mut regions: Vec<Vec<Cube>> = Vec::new();
+
+        for start in 0..self.hexes.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            let mut region: Vec<Cube> = Vec::new();
+            let mut queue: VecDeque<usize> = VecDeque::new();
+            queue.push_back(start);
+
+            while let Some(index) = queue.pop_front() {
+                let (cube, data) = &self.hexes[index];
+                region.push(*cube);
+
+                for neighbour in cube.neighbours().iter() {
+                    if let Some(n_index) = self.index.get(neighbour) {
+                        let n_index = *n_index;
+                        if !visited[n_index] && (connected)(data, &self.hexes[n_index].1) {
+                            visited[n_index] = true;
+                            queue.push_back(n_index);
+                        }
+                    }
+                }
+            }
+
+            regions.push(region);
+        }
+
+        regions
+    }
+
     fn iter(&self) -> impl Iterator<Item = HexTile<T>> {
         self.hexes
             .iter()
@@ -195,6 +232,13 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash> Grid<T> {
         self.shape
     }
 
+    /// Split the grid into connected regions. Two neighbouring hexes belong to the same
+    /// region if `connected` returns true for their data. Every hex ends up in exactly one
+    /// region even if it's a region of one.
+    pub fn regions<F: Fn(&T, &T) -> bool>(&self, connected: F) -> Vec<Vec<Cube>> {
+        self.inner.regions(connected)
+    }
+
     /// Will clone a copy of the `Rectangular<T>` grid and iterate through all hexagons
     /// applying the sent function/closure. Function takes a reference to the coordinate
     /// that the 
@@ -470,6 +514,33 @@ mod test {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn regions_3x3() {
+        // Left column against the rest.
+        let r_grid: Grid<char> = Rectangular::generate_with(3, 3, |cube| {
+            if [(0, 0), (0, 1), (-1, 2)].iter().any(|c| Cube::from(*c) == *cube) {
+                'A'
+            } else {
+                'B'
+            }
+        }).into();
+
+        let mut regions = r_grid.regions(|a, b| a == b);
+        regions.sort_by_key(|region| region.len());
+
+        assert!(regions.len() == 2);
+        assert!(regions[0].len() == 3);
+        assert!(regions[1].len() == 6);
+    }
+
+    #[test]
+    fn regions_disconnected() {
+        let r_grid: Grid<u32> = increment_generator(2, 2);
+        let regions = r_grid.regions(|a, b| a == b);
+
+        assert!(regions.len() == 4);
+    }
+
     #[test]
     fn fork_2x2_grid() {
         let r_grid: Grid<u32> = Rectangular::generate(2, 2, 4).into();
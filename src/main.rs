@@ -12,7 +12,7 @@ fn main() -> Result<(), Box<dyn error::Error + 'static>> {
     println!("Dicey Dice starting...");
 
     let players = game::Players::new(2);
-    let start = game::generate_random_board(8, 8, players);
+    let start: game::Board = game::generate_random_board(8, 8, players);
     
     //let start = game::canned_2x2_start04();
     //let start = game::canned_3x1_start05();
@@ -41,6 +41,7 @@ fn main() -> Result<(), Box<dyn error::Error + 'static>> {
     let session = session::Setup::new()
         .set_board(start)
         //.set_move_limit(NonZeroU8::new(2).unwrap())
+        //.set_ruleset(*game::Ruleset::new().set_pass(game::PassRule::Anytime))
         .session()?;
 
     console::play_session(session);
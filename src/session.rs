@@ -1,18 +1,18 @@
 //! Handle a game.
-use std::num::NonZeroU8;
-use std::fmt;
+use std::num::{NonZeroU8, NonZeroUsize};
+use std::{fmt, iter};
 
 use derive_getters::Getters;
-use rand::{rngs, Rng};
+use rand::{rngs, Rng, SeedableRng};
 
-use crate::game::{self, Tree, Board, Players, Player, Choice, Action, Consequence, Holding};
+use crate::game::{
+    self, Tree, Board, Players, Player, Choice, Action, Consequence, Ruleset, Placement,
+    TieRule, Die, Ranking, TieBreaker, BoardView, Holding, Handicap,
+};
 
-fn roll_d6s<T: Rng>(d6s: u8, random: &mut T) -> usize {
-    (0..d6s)
-        .fold(0, |sum, _| -> usize {
-            sum + random.gen_range(1, 7)
-        })
-}
+mod roller;
+
+pub use roller::{DiceRoller, SeededRoller, ScriptedRoller};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct LastAttack {
@@ -20,13 +20,45 @@ pub struct LastAttack {
     attacker_rolled: usize,
     defender_dice: u8,
     defender_rolled: usize,
+    attacker_won: bool,
+
+    /// The rule that decided the attack if the first rolls were tied.
+    tie: Option<TieRule>,
+
+    /// The players on either side of the attack. Neutral when there wasn't one.
+    attacker: Player,
+    defender: Player,
 }
 
 impl LastAttack {
     fn new(
-        attacker_dice: u8, attacker_rolled: usize, defender_dice: u8, defender_rolled: usize
+        attacker_dice: u8,
+        attacker_rolled: usize,
+        defender_dice: u8,
+        defender_rolled: usize,
+        attacker_won: bool,
     ) -> Self {
-        LastAttack { attacker_dice, attacker_rolled, defender_dice, defender_rolled }
+        LastAttack {
+            attacker_dice, attacker_rolled, defender_dice, defender_rolled, attacker_won,
+            tie: None,
+            attacker: Player::neutral(),
+            defender: Player::neutral(),
+        }
+    }
+
+    /// Record who attacked whom.
+    fn with_sides(self, attacker: Player, defender: Player) -> Self {
+        LastAttack { attacker, defender, ..self }
+    }
+
+    /// Whether `player` attacked or defended.
+    fn involves(&self, player: Player) -> bool {
+        self.attacker == player || self.defender == player
+    }
+
+    /// Record that the first rolls were tied and the `tie` rule decided the attack.
+    fn with_tie(self, tie: TieRule) -> Self {
+        LastAttack { tie: Some(tie), ..self }
     }
 }
 
@@ -35,7 +67,7 @@ impl fmt::Display for LastAttack {
         if self.attacker_rolled == 0 && self.defender_rolled == 0 {
             write!(f, "") // Sentinel value for first turn thus no preceding attacks.
         } else {
-            if self.attacker_rolled > self.defender_rolled {
+            if self.attacker_won {
                 write!(
                     f,
                     "Attacker with {} dice rolled {} beating \
@@ -44,7 +76,7 @@ impl fmt::Display for LastAttack {
                     &self.attacker_rolled,
                     &self.defender_dice,
                     &self.defender_rolled,
-                )
+                )?;
             } else {
                 write!(
                     f,
@@ -54,7 +86,14 @@ impl fmt::Display for LastAttack {
                     &self.defender_rolled,
                     &self.attacker_dice,
                     &self.attacker_rolled,
-                )
+                )?;
+            }
+
+            match self.tie {
+                Some(TieRule::DefenderWins) => write!(f, " Tie goes to the defender."),
+                Some(TieRule::AttackerWins) => write!(f, " Tie goes to the attacker."),
+                Some(TieRule::Reroll) => write!(f, " Rolled again after a tie."),
+                None => Ok(()),
             }
         }
     }
@@ -62,42 +101,147 @@ impl fmt::Display for LastAttack {
 
 impl Default for LastAttack {
     fn default() -> Self {
-        LastAttack::new(0, 0, 0, 0)
+        LastAttack::new(0, 0, 0, 0, false)
+    }
+}
+
+/// Who won the game. When teams are set up, the team wins together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Winner {
+    Player(Player),
+    Team(Vec<Player>),
+}
+
+impl Winner {
+    /// The winner being the current player of the won `board`.
+    fn from_board<H: Holding>(board: &Board<H>) -> Self {
+        Winner::from_player(board, board.players().current())
+    }
+
+    /// The winner being `player` along with their team on the `board`.
+    fn from_player<H: Holding>(board: &Board<H>, player: Player) -> Self {
+        let team = board.players().team_members(player);
+        if team.len() > 1 {
+            Winner::Team(team)
+        } else {
+            Winner::Player(player)
+        }
+    }
+}
+
+impl fmt::Display for Winner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Winner::Player(player) => write!(f, "{}", player),
+            Winner::Team(players) => {
+                let team = players
+                    .iter()
+                    .map(|player| player.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "team {}", &team)
+            },
+        }
     }
 }
 
+/// Final standings of a finished game for ladders and tournaments.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct Results {
+    /// Every player from first to last. The winners share first place. Players still in
+    /// the game at the end come next, then those who went out with the last out first.
+    placings: Ranking,
+
+    /// Players that went out with the turn they went out on, first out first.
+    eliminations: Vec<(Player, usize)>,
+}
+
 /// State of game progression. Whether the game is on, over and what kind of over.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Progression {
     PlayOn(LastAttack),
-    GameOverWinner(Player),
-    GameOverStalemate(Vec<Player>), // Easier to calculate than a draw...
+    GameOverWinner(Winner),
+    GameOverStalemate(Ranking), // Easier to calculate than a draw...
+
+    /// The round limit was reached. Players are ranked by the session's scoring rule.
+    GameOverRoundLimit(Ranking),
+
+    /// A player reached the victory points the ruleset asks for by holding objectives.
+    GameOverVictoryPoints(Winner),
 }
 
 /// The state of the session.
 #[derive(Debug, Clone, Getters)]
-pub struct State {
+pub struct State<H: Holding = u8> {
     /// Whether we continue play or not.
     game: Progression,
     
     /// If a bunch of single move turns needed to be made first. These include players
     /// being knocked out or players only being able to pass their turn.
-    traversal: Vec<(Board, Choice)>,
+    traversal: Vec<(Board<H>, Choice<H>)>,
 
     /// State of game.
-    board: Board,
+    board: Board<H>,
 
     /// Choices available to current player.
-    choices: Vec<Choice>,
+    choices: Vec<Choice<H>>,
+
+    /// Reinforcements banked by each player still in the game.
+    reserves: Vec<(Player, u8)>,
+}
+
+/// A `State` redacted for one player with `State::view_for`.
+#[derive(Debug, Clone, Getters)]
+pub struct StateView {
+    game: Progression,
+    board: BoardView,
+
+    /// The actions on offer. Empty unless it's the viewing player's turn.
+    actions: Vec<Action>,
 }
 
-impl State {
+impl<H: Holding> State<H> {
+    /// The state as `player` is allowed to see it. Only includes the actions, not the
+    /// boards they lead to, as those would leak the whole grid. The rolls of the last
+    /// attack are only shown to the attacker and defender since the dice on either side
+    /// could give away hexes hidden from everyone else.
+    pub fn view_for(&self, player: Player) -> StateView {
+        let game = match self.game {
+            Progression::PlayOn(outcome) if !outcome.involves(player) => {
+                Progression::PlayOn(LastAttack::default())
+            },
+            ref game => game.clone(),
+        };
+
+        let actions = if self.board.players().current() == player {
+            self.choices
+                .iter()
+                .map(|choice| *choice.action())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        StateView {
+            game,
+            board: self.board.view_for(player),
+            actions,
+        }
+    }
+
     fn new(
         game: Progression,
-        traversal: &[(Board, Choice)],
-        board: Board,
-        choices: &[Choice],
+        traversal: &[(Board<H>, Choice<H>)],
+        board: Board<H>,
+        choices: &[Choice<H>],
     ) -> Self {
+        let reserves = board
+            .players()
+            .playing()
+            .into_iter()
+            .map(|player| (player, board.reserve(player)))
+            .collect();
+        
         State {
             game,
             traversal: traversal
@@ -109,30 +253,42 @@ impl State {
                 .into_iter()
                 .map(|c| c.to_owned())
                 .collect(),
+            reserves,
         }
     }
 }
 
+/// A board missing from the `Tree` along with the `traversal` made up to it.
+type Stuck<H> = Box<(Board<H>, Vec<(Board<H>, Choice<H>)>)>;
+
 /// Generate a `State` from a chosen `Board` consequence and the `Tree` where that `Board`
 /// must exist. Runs inside a loop skipping over states that have only one turn left in
-/// them except for Winning states. Uses some logic to detect draw states.
-fn state_from_board(
-    mut current_board: Board, tree: &Tree, outcome: LastAttack,
-) -> Result<State, usize> {
-    let mut traversal: Vec<(Board, Choice)> = Vec::new();   
-    let mut depth: usize = 1;
-    
+/// them except for Winning states. Uses some logic to detect draw states. Skipped choices
+/// are carried out by `follow` which returns the board the choice leads to. If a board
+/// isn't in the `Tree`, it is returned along with the `traversal` made so far.
+fn state_from_board<H, F>(
+    mut current_board: Board<H>,
+    mut traversal: Vec<(Board<H>, Choice<H>)>,
+    tree: &Tree<H>,
+    ruleset: &Ruleset,
+    outcome: LastAttack,
+    follow: &mut F,
+) -> Result<State<H>, Stuck<H>>
+where
+    H: Holding,
+    F: FnMut(&Board<H>, &Choice<H>) -> Board<H>,
+{
     let state = loop {
-        let choices = tree
-            .fetch_choices(&current_board)
-            .ok_or(depth)?;
+        let choices = match tree.fetch_choices(&current_board) {
+            Some(choices) => choices,
+            None => return Err(Box::new((current_board, traversal))),
+        };
         
         // If there's only one choice left, it may be a passing/gameover/win move. Or the
         // last available attack.
         if choices.len() == 1 {
-            depth += 1;
             match choices[0].action() {
-                Action::Attack(_, _, _, _) => {
+                Action::Attack(_, _, _, _) | Action::Transfer(_, _, _) => {
                     // There is one last attack to make. We won't execute this choice
                     // for the player as that'd be overstepping our bounds. Thus we jump
                     // out of this loop.
@@ -143,39 +299,49 @@ fn state_from_board(
                         choices,
                     );
                 },
-                Action::Pass => {
-                    // It'd be cumbersome to manually pass a move. The player can't "do"
-                    // anything. So let's just deal with it automatically.
+                Action::Pass | Action::Reinforce(_) => {
+                    // It'd be cumbersome to manually pass a move or place a die on the
+                    // only hex with room. The player can't "do" anything. So let's just
+                    // deal with it automatically.
 
                     // In order to do this, we need to figure out the passing consequence.
                     match choices[0].consequence() {
                         Consequence::Stalemate(next_board) => break State::new(
-                            Progression::GameOverStalemate(next_board.players().playing()),
+                            Progression::GameOverStalemate(
+                                game::rank_players(next_board, ruleset.tie_breaker()),
+                            ),
                             traversal.as_slice(),
                             next_board.to_owned(),
                             choices,
                         ),
                         Consequence::Winner(next_board) => break State::new(
-                            Progression::GameOverWinner(next_board.players().current()),
+                            Progression::GameOverWinner(Winner::from_board(next_board)),
                             traversal.as_slice(),
                             next_board.to_owned(),
                             choices,
                         ),
-                        Consequence::GameOver(next_board) => {
-                            // We need to iterate the progression.
-                            traversal.push((current_board, choices[0].to_owned()));
-                            current_board = next_board.to_owned();
-                            continue;
-                        },
-                        Consequence::TurnOver(next_board) => {
+                        Consequence::VictoryPoints(next_board) => break State::new(
+                            Progression::GameOverVictoryPoints(Winner::from_player(
+                                next_board, game::points_leader(next_board),
+                            )),
+                            traversal.as_slice(),
+                            next_board.to_owned(),
+                            choices,
+                        ),
+                        Consequence::GameOver(_)
+                            | Consequence::TurnOver(_)
+                            | Consequence::Continue(_) => {
                             // We need to iterate the progression.
+                            let next_board = follow(&current_board, &choices[0]);
                             traversal.push((current_board, choices[0].to_owned()));
-                            current_board = next_board.to_owned();
+                            current_board = next_board;
                             continue;
                         },
-                        Consequence::Continue(_) => unreachable!(),
                     }
                 },
+                Action::Resign(_) => {
+                    unreachable!("Resigning is never a choice, see `Session::resign`.")
+                },
             }
         }
 
@@ -191,6 +357,20 @@ fn state_from_board(
     Ok(state)
 }
 
+/// The board that `choice` leads to from the board `from`. Passing the turn with
+/// `Placement::Random` rolls where the reinforcements land. Otherwise it's the board the
+/// `Tree` already worked out.
+fn follow_choice<H: Holding>(
+    from: &Board<H>, choice: &Choice<H>, ruleset: &Ruleset, roller: &mut dyn DiceRoller,
+) -> Board<H> {
+    match (choice.action(), choice.consequence(), ruleset.placement()) {
+        (Action::Pass, Consequence::TurnOver(_), Placement::Random) => {
+            game::board_from_pass(from, ruleset, &mut |choices| roller.pick(choices))
+        },
+        _ => choice.consequence().board().to_owned(),
+    }
+}
+
 /// A game in progress. The `traversals` indicate how many turns have passed. Maintains
 /// all state of the game.
 ///
@@ -199,57 +379,133 @@ fn state_from_board(
 /// 2. The first `State` in the `turns` is the starting position sans any inital traversals.
 /// 3. There will always be at least one `State` in the `turns`.
 #[derive(Debug, Clone, Getters)]
-pub struct Session {
-    turns: Vec<State>,
-    tree: Option<Tree>,
-    move_limit: NonZeroU8,
-    rand: rngs::ThreadRng,
+pub struct Session<H: Holding = u8> {
+    turns: Vec<State<H>>,
+    tree: Option<Tree<H>>,
+    ruleset: Ruleset,
+    roller: Box<dyn DiceRoller>,
+
+    /// The round being played. A round is over once every player has had a turn.
+    round: usize,
+
+    /// The game ends once this many rounds have been played.
+    round_limit: Option<NonZeroUsize>,
+
+    /// How the players are ranked when the round limit is reached.
+    round_scoring: TieBreaker,
 }
 
-impl Session {
-    pub fn new(start: Board, tree: Tree, move_limit: NonZeroU8) -> Self {
+impl<H: Holding> Session<H> {
+    pub fn new(
+        start: Board<H>, tree: Tree<H>, ruleset: Ruleset, roller: Box<dyn DiceRoller>,
+    ) -> Self {
+        let mut session = Session {
+            turns: Vec::new(),
+            tree: Some(tree),
+            ruleset,
+            roller,
+            round: 1,
+            round_limit: None,
+            round_scoring: TieBreaker::default(),
+        };
+
         // The start may contain pass move. Cycle to get at the first true turn.
-        // This code is a copy of what's happening in `advance` below. TODO: Refactor me.
-        
-        let mut tree = Some(tree);
-        let first_turn = loop {
+        let first_turn = session.resolve(start, LastAttack::default());
+        session.push_turn(first_turn);
+        session
+    }
+
+    /// End the game after `round_limit` rounds ranking the players with `round_scoring`.
+    fn with_round_limit(
+        self, round_limit: Option<NonZeroUsize>, round_scoring: TieBreaker,
+    ) -> Self {
+        Session { round_limit, round_scoring, ..self }
+    }
+
+    /// Add the next turn counting off any rounds that passed in getting to it. Ends the
+    /// game if that goes over the round limit.
+    fn push_turn(&mut self, mut state: State<H>) {
+        let mut previous = self.turns.last().map(|turn| *turn.board.players());
+        let boards = state.traversal
+            .iter()
+            .map(|(board, _)| board)
+            .chain(iter::once(&state.board));
+
+        // Play wrapping back around to a lower numbered player starts a new round.
+        for board in boards {
+            let players = board.players();
+            if let Some(previous) = previous {
+                if players.current().number() < previous.current().number() {
+                    self.round += 1;
+                }
+            }
+            previous = Some(*players);
+        }
+
+        if let (Some(limit), Progression::PlayOn(_)) = (self.round_limit, &state.game) {
+            if self.round > limit.get() {
+                let ranking = game::rank_players(&state.board, &self.round_scoring);
+                state.game = Progression::GameOverRoundLimit(ranking);
+                state.choices.clear();
+            }
+        }
+
+        self.turns.push(state);
+    }
+
+    /// Work out the `State` from `board` skipping any single choice turns. Grows the tree
+    /// whenever the traversal runs off the end of it.
+    fn resolve(&mut self, board: Board<H>, outcome: LastAttack) -> State<H> {
+        let Session { tree, ruleset, roller, .. } = self;
+        let mut follow = |from: &Board<H>, choice: &Choice<H>| {
+            follow_choice(from, choice, ruleset, roller.as_mut())
+        };
+
+        let mut board = board;
+        let mut traversal = Vec::new();
+        loop {
             match state_from_board(
-                start.clone(), tree.as_ref().unwrap(), LastAttack::default()
+                board, traversal, tree.as_ref().unwrap(), ruleset, outcome, &mut follow,
             ) {
-                Ok(state) => break state,
-                Err(depth) => {
+                Ok(state) => return state,
+                Err(stuck) => {
+                    let (stuck, so_far) = *stuck;
                     let new_tree = game::start_tree_horizon_limited(
-                        start.clone(), depth, move_limit.get(),
+                        stuck.clone(), 1, ruleset,
                     );
-                    tree = Some(new_tree);
+                    *tree = Some(new_tree);
+                    board = stuck;
+                    traversal = so_far;
                 },
             }
-        };
-        
-        Session {
-            turns: vec![first_turn],
-            tree,
-            move_limit,
-            rand: rand::thread_rng(),
         }
     }
 
+    /// Start over from the first turn. The roller carries on from where it was so the
+    /// replayed game won't roll the same as before.
     pub fn reset(self) -> Self {
         let first = self.turns.first().unwrap().board.to_owned();
         Session::new(
             first.clone(),
-            game::start_tree_horizon_limited(first, 1, self.move_limit.get()),
-            self.move_limit,
-        )
+            game::start_tree_horizon_limited(first, 1, &self.ruleset),
+            self.ruleset,
+            self.roller,
+        ).with_round_limit(self.round_limit, self.round_scoring)
     }
             
-    pub fn current_turn(&self) -> &State {
+    pub fn current_turn(&self) -> &State<H> {
         self.turns.last().unwrap()
     }
 
+    /// The current turn as `player` is allowed to see it under fog of war.
+    pub fn view_for(&self, player: Player) -> StateView {
+        self.current_turn().view_for(player)
+    }
+
     /// Take an `Action` and advance the game state. Advances the tree if necessary. Takes
-    /// an `index` of the `[Choice]`. The `Choice` will always be an attacking action.
-    pub fn advance(&mut self, index: usize) -> Result<&State, String> {
+    /// an `index` of the `[Choice]`. The `Choice` will be an attacking action unless the
+    /// `PassRule` lets the player end their turn early or they're placing reinforcements.
+    pub fn advance(&mut self, index: usize) -> Result<&State<H>, String> {
         let choice = self
             .current_turn()
             .choices()
@@ -257,67 +513,184 @@ impl Session {
             .ok_or("Index out of bounds.".to_owned())?
             .to_owned();
 
-        let (attacker_coordinate, attacker_dice, defender_dice) = match choice.action() {
-            Action::Attack(ac, _, ad, dd) => (*ac, *ad, *dd),
-            Action::Pass => unreachable!(), // Must never happen. `Session` must always
-                                            // return with attack choices or game over.
+        let (next_board, outcome) = match choice.action() {
+            Action::Attack(_, to, ad, dd) => {
+                let grid = self.current_turn().board.grid();
+                let defender = grid.fetch(to).expect("Invalid to coordinate.").owner();
+                // Terrain can give the defender extra dice to roll.
+                let bonus = game::defence_bonus(grid, *to);
+                self.attack(&choice, defender, *ad, *dd + bonus)
+            },
+            Action::Pass | Action::Reinforce(_) | Action::Transfer(_, _, _) => {
+                // Player ended their turn, placed or moved dice. There's no roll to make.
+                let from = self.current_turn().board.to_owned();
+                let next_board = follow_choice(
+                    &from, &choice, &self.ruleset, self.roller.as_mut(),
+                );
+                (next_board, LastAttack::default())
+            },
+            Action::Resign(_) => {
+                unreachable!("Resigning is never a choice, see `Session::resign`.")
+            },
+        };
+        
+        let state = self.resolve(next_board, outcome);
+        self.push_turn(state);
+        Ok(self.current_turn())
+    }
+
+    /// Take `player` out of the game whether it's their turn or not. Their hexes turn
+    /// neutral or stay frozen according to the `Resignation` rule. The resignation heads
+    /// the traversal of the turn that follows. If only one team is left they win.
+    pub fn resign(&mut self, player: Player) -> Result<&State<H>, String> {
+        match self.current_turn().game {
+            Progression::PlayOn(_) => (),
+            _ => return Err("The game is over.".to_owned()),
+        }
+
+        let from = self.current_turn().board.to_owned();
+        if !from.players().playing().contains(&player) {
+            return Err(format!("Player {} is not playing.", player));
+        }
+
+        let next_board = game::board_from_resign(&from, player, &self.ruleset);
+        let resignation = Choice::new(
+            Action::Resign(player), Consequence::GameOver(next_board.clone()),
+        );
+
+        let players = *next_board.players();
+        let last_team = players
+            .playing()
+            .into_iter()
+            .all(|other| players.allies(other, players.current()));
+        let state = if last_team {
+            State::new(
+                Progression::GameOverWinner(Winner::from_board(&next_board)),
+                &[(from, resignation)],
+                next_board,
+                &[],
+            )
+        } else {
+            let mut state = self.resolve(next_board, LastAttack::default());
+            state.traversal.insert(0, (from, resignation));
+            state
         };
 
-        let attacker_roll = roll_d6s(attacker_dice, &mut self.rand);
-        let defender_roll = roll_d6s(defender_dice, &mut self.rand);
+        self.push_turn(state);
+        Ok(self.current_turn())
+    }
+
+    /// The final placings of every player once the game is over. `None` while the game
+    /// is still being played.
+    pub fn results(&self) -> Option<Results> {
+        let state = self.current_turn();
+        let board = &state.board;
+        let eliminations = board.players().eliminations();
+
+        // Those still in the game are ranked above anyone who went out. Only they are told
+        // apart by their victory points. The points only have to keep the order, not mean
+        // anything.
+        let above = eliminations.len() + 1;
+        let standing: Vec<(Player, usize)> = match &state.game {
+            Progression::PlayOn(_) => return None,
+            Progression::GameOverWinner(winner)
+                | Progression::GameOverVictoryPoints(winner) => {
+                let winners = match winner {
+                    Winner::Player(player) => vec![*player],
+                    Winner::Team(team) => team.clone(),
+                };
+                let top = above + u8::max_value() as usize + 1;
+                board
+                    .players()
+                    .playing()
+                    .into_iter()
+                    .filter(|player| !winners.contains(player))
+                    .map(|player| (player, above + board.points(player) as usize))
+                    .chain(winners.iter().map(|player| (*player, top)))
+                    .collect()
+            },
+            Progression::GameOverStalemate(ranking)
+                | Progression::GameOverRoundLimit(ranking) => {
+                let last = ranking.placings().len();
+                ranking
+                    .placings()
+                    .iter()
+                    .map(|(player, place)| (*player, above + last - place))
+                    .collect()
+            },
+        };
+
+        let out = eliminations
+            .iter()
+            .enumerate()
+            .filter(|(_, (player, _))| standing.iter().all(|(other, _)| other != player))
+            .map(|(index, (player, _))| (*player, index + 1));
+        let mut points: Vec<(Player, usize)> = standing
+            .iter()
+            .cloned()
+            .chain(out)
+            .collect();
+        points.sort_by_key(|(player, _)| *player.number());
+
+        Some(Results {
+            placings: Ranking::from_points(points),
+            eliminations,
+        })
+    }
+
+    /// Roll for the attack on `defender` returning the resulting board and outcome.
+    fn attack(
+        &mut self,
+        choice: &Choice<H>,
+        defender: Player,
+        attacker_dice: u8,
+        defender_dice: u8,
+    ) -> (Board<H>, LastAttack) {
+        let faces = self.ruleset.die().faces();
+        let tie = *self.ruleset.tie();
+        let mut tied = false;
 
+        let (attacker_roll, defender_roll) = loop {
+            let attacker_roll = self.roller.roll(attacker_dice, faces);
+            let defender_roll = self.roller.roll(defender_dice, faces);
+
+            tied = tied || attacker_roll == defender_roll;
+            if tie == TieRule::Reroll && attacker_roll == defender_roll {
+                continue;
+            }
+            break (attacker_roll, defender_roll);
+        };
+
+        let attacker_won = tie.attacker_wins(attacker_roll, defender_roll);
         let outcome = LastAttack::new(
-            attacker_dice, attacker_roll, defender_dice, defender_roll
+            attacker_dice, attacker_roll, defender_dice, defender_roll, attacker_won,
         );
+        let outcome = if tied { outcome.with_tie(tie) } else { outcome };
+        let attacker = self.current_turn().board.players().current();
+        let outcome = outcome.with_sides(attacker, defender);
         
-        let next_board = if attacker_roll > defender_roll {
+        let next_board = if attacker_won {
             // Board advances due to win.
             choice.consequence().board().to_owned()
         } else {
-            // Board stays the same sans one move due to loss and the losing hex frozen.
-            let current_board = &self.current_turn().board;
-            Board::new(
-                *current_board.players(),
-                current_board
-                    .grid()
-                    .fork_with(|coordinate, hold| {
-                        // Freeze the losing hex til next turn.
-                        if coordinate == &attacker_coordinate {
-                            u8::new(hold.owner(), hold.dice(), false)
-                        } else {
-                            hold
-                        }
-                    }),
-                *current_board.captured_dice(),
-                *current_board.moved() + 1,
+            // Board stays the same sans one move due to loss and the losing hex penalized.
+            game::board_from_failed_attack(
+                &self.current_turn().board,
+                *choice.action(),
+                *self.ruleset.failure_penalty(),
             )
         };
-        
-        let state = loop {
-            match state_from_board(
-                next_board.clone(), &self.tree.as_ref().unwrap(), outcome,
-            ) {
-                Ok(state) => break state,
-                Err(depth) => {
-                    let new_tree = game::start_tree_horizon_limited(
-                        next_board.to_owned(), depth, self.move_limit.get(),
-                    );
-                    self.tree = Some(new_tree);
-                },
-            }
-        };
-        
-        self.turns.push(state);
-        Ok(self.current_turn())
+
+        (next_board, outcome)
     }
 
     /// Score the tree up to the depth specified in `horizon`. Will then edit current
     /// `State` to put the scoring into the current choices. A deep horizon will cause the
     /// system to lock up. High chance that an OOM error will follow.
-    pub fn score_with_depth_horizon(&mut self, horizon: usize) -> &State {
+    pub fn score_with_depth_horizon(&mut self, horizon: usize) -> &State<H> {
         let current_board = self.current_turn().board.to_owned();
         let tree = game::start_tree_horizon_limited(
-            current_board, horizon, self.move_limit.get(),
+            current_board, horizon, &self.ruleset,
         );
         
         let _ = game::score_tree(&tree);
@@ -331,10 +704,10 @@ impl Session {
     /// Score the tree up to the the board insert budget specified. The first tree layer
     /// though will be computed without taking into account the budget, this way there will
     /// always be all available choices for the turn.
-    pub fn score_with_insert_budget(&mut self, insert_budget: usize) -> &State {
+    pub fn score_with_insert_budget(&mut self, insert_budget: usize) -> &State<H> {
         let current_board = self.current_turn().board.to_owned();
         let tree = game::start_tree_insert_budgeted(
-            current_board, insert_budget, self.move_limit.get(),
+            current_board, insert_budget, &self.ruleset,
         );
         
         let _ = game::score_tree(&tree);
@@ -347,23 +720,33 @@ impl Session {
 }
 
 /// Setup a game session. Can set the number of players and the board size and to use
-/// canned boards (feed it a starting position. The board can only be rectangular.
+/// canned boards (feed it a starting position. The board can only be rectangular. Boards
+/// hold `u8`s unless set up with `Setup::default` for a wider `Holding`.
 #[derive(Debug, Clone, Getters)]
-pub struct Setup {
+pub struct Setup<H: Holding = u8> {
     players: Players,
-    board: Option<Board>,
-    move_limit: NonZeroU8,
+    board: Option<Board<H>>,
+    ruleset: Ruleset,
+    roller: Box<dyn DiceRoller>,
+    round_limit: Option<NonZeroUsize>,
+    round_scoring: TieBreaker,
+    teams: Vec<(Player, usize)>,
+
+    /// Chance from 0 to 1 of each generated hex being neutral.
+    neutrals: f64,
+
+    /// Seeds the generated boards as well as the dice when set.
+    seed: Option<u64>,
 }
 
 impl Setup {
+    /// Boards hold `u8`s. Good for up to 7 players with 7 dice a hex.
     pub fn new() -> Self {
-        Setup {
-            players: Players::new(2),
-            board: None,
-            move_limit: NonZeroU8::new(6).unwrap(),
-        }
+        Setup::default()
     }
+}
 
+impl<H: Holding> Setup<H> {
     /// If the number of players changes in any way, it will invalidate the `Board`.
     pub fn set_players(&mut self, players: Players) -> &mut Self {        
         if self.players != players {
@@ -373,41 +756,153 @@ impl Setup {
         self
     }
 
+    /// Shortcut for setting the move limit on the `Ruleset`.
     pub fn set_move_limit(&mut self, move_limit: NonZeroU8) -> &mut Self {
-        self.move_limit = move_limit;
+        self.ruleset.set_move_limit(move_limit);
+        self
+    }
+
+    /// Shortcut for setting the kind of dice rolled on the `Ruleset`.
+    pub fn set_die(&mut self, die: Die) -> &mut Self {
+        self.ruleset.set_die(die);
+        self
+    }
+
+    /// Shortcut for setting who wins tied rolls on the `Ruleset`.
+    pub fn set_tie(&mut self, tie: TieRule) -> &mut Self {
+        self.ruleset.set_tie(tie);
+        self
+    }
+
+    /// Shortcut for setting the victory points needed to win on the `Ruleset`.
+    pub fn set_victory_points(&mut self, victory_points: u8) -> &mut Self {
+        self.ruleset.set_victory_points(victory_points);
+        self
+    }
+
+    /// End the game once `round_limit` rounds have been played. The players are then
+    /// ranked by `round_scoring`. Keeps games on big boards from dragging on.
+    pub fn set_round_limit(
+        &mut self, round_limit: NonZeroUsize, round_scoring: TieBreaker,
+    ) -> &mut Self {
+        self.round_limit = Some(round_limit);
+        self.round_scoring = round_scoring;
+        self
+    }
+
+    /// Shortcut for handicapping `player` on the `Ruleset`. The hexes and dice of the
+    /// `handicap` only count towards boards made with `gen_board` after this is set.
+    pub fn set_handicap(&mut self, player: Player, handicap: Handicap) -> &mut Self {
+        self.ruleset.set_handicap(player, handicap);
+        self
+    }
+
+    /// Put `player` into `team`. Players on the same team can't attack each other and
+    /// win together. Players not given a team play on their own.
+    pub fn set_team(&mut self, player: Player, team: usize) -> &mut Self {
+        self.teams.push((player, team));
+        self
+    }
+
+    /// Play by house rules.
+    pub fn set_ruleset(&mut self, ruleset: Ruleset) -> &mut Self {
+        self.ruleset = ruleset;
+        self
+    }
+
+    /// Roll the dice from a `SeededRoller` using `seed`. Sessions made with the same seed
+    /// and board will play out identically given the same choices. Boards generated after
+    /// this are dealt out from the seed too.
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.roller = Box::new(SeededRoller::new(seed));
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Use a custom `DiceRoller`. Handy for scripting rolls in tests.
+    pub fn set_roller(&mut self, roller: Box<dyn DiceRoller>) -> &mut Self {
+        self.roller = roller;
         self
     }
 
     /// Set the board. This will also set the players since the `Board` lists all state.
-    pub fn set_board(&mut self, board: Board) -> &mut Self {
+    pub fn set_board(&mut self, board: Board<H>) -> &mut Self {
         self.players = *board.players();
         self.board = Some(board);
         self
     }
 
-    /// Will generate a new board using the loaded in `Players` setting.
+    /// Will generate a new board using the loaded in `Players` setting. No board is made
+    /// when there are more players than the `Holding` can store. Uses the seed if one
+    /// was set.
     pub fn gen_board(&mut self, columns: u32, rows: u32) -> &mut Self {
-        self.board = Some(game::generate_random_board(columns, rows, self.players));
+        if self.players.total() > H::PLAYER_LIMIT {
+            self.board = None;
+            return self;
+        }
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = rngs::StdRng::seed_from_u64(seed);
+        let grid = game::generate_handicapped_grid(
+            columns,
+            rows,
+            self.players,
+            self.neutrals,
+            &self.ruleset,
+            &mut rng,
+        );
+        self.board = Some(Board::new(self.players, grid, 0, 0));
+        self
+    }
+
+    /// Seed generated boards with neutral hexes. Each hex has a `neutrals` chance from 0
+    /// to 1 of belonging to nobody.
+    pub fn set_neutrals(&mut self, neutrals: f64) -> &mut Self {
+        self.neutrals = neutrals;
         self
     }
 
-    /// Produce a game session! Will return an error if there is no `Board` setup. Boards
-    /// greater than 3x3 will hang the system as the current state of the library is to
-    /// 'solve' the game by resolving the entire tree of every possible action.
-    pub fn session(&self) -> Result<Session, String> {
+    /// Produce a game session! Will return an error if there is no `Board` setup or if
+    /// there are more players than the `Holding` can store. Boards greater than 3x3 will
+    /// hang the system as the current state of the library is to 'solve' the game by
+    /// resolving the entire tree of every possible action.
+    pub fn session(&self) -> Result<Session<H>, String> {
+        if self.players.total() > H::PLAYER_LIMIT {
+            return Err(format!(
+                "{} players don't fit a holding of {}.",
+                self.players.total(),
+                H::PLAYER_LIMIT,
+            ));
+        }
         if let Some(board) = self.board.clone() {
-            let tree = game::start_tree_horizon_limited(
-                board.clone(), 1, self.move_limit.get());
-            Ok(Session::new(board, tree, self.move_limit))
+            let players = self.teams
+                .iter()
+                .fold(*board.players(), |players, (player, team)| {
+                    players.with_team(*player, *team)
+                });
+            let board = board.with_players(players);
+            let tree = game::start_tree_horizon_limited(board.clone(), 1, &self.ruleset);
+            let session = Session::new(board, tree, self.ruleset, self.roller.clone())
+                .with_round_limit(self.round_limit, self.round_scoring);
+            Ok(session)
         } else {
             Err("No board set.".to_owned())
         }
     }
 }
 
-impl Default for Setup {
+impl<H: Holding> Default for Setup<H> {
     fn default() -> Self {
-        Setup::new()
+        Setup {
+            players: Players::new(2),
+            board: None,
+            ruleset: Ruleset::default(),
+            roller: Box::new(SeededRoller::from_entropy()),
+            round_limit: None,
+            round_scoring: TieBreaker::default(),
+            teams: Vec::new(),
+            neutrals: 0_f64,
+            seed: None,
+        }
     }
 }
 
@@ -416,6 +911,10 @@ mod test {
     use std::error;
 
     use crate::{game, session};
+    use crate::hexagon::{Cube, Grid};
+    use crate::game::{
+        Holding, PassRule, FailurePenalty, Placement, Reinforcement, Terrain, Resignation,
+    };
     
     use super::*;
 
@@ -423,10 +922,15 @@ mod test {
     fn state_from_board_2x1() -> Result<(), Box<dyn error::Error>> {
         let start = game::canned_2x1_start01();
         let s_grid = start.grid().to_owned();
-        let tree = game::build_tree(start.clone(), 10);
+        let tree = game::build_tree(start.clone(), &Ruleset::new());
         let last_attack = LastAttack::default();
 
-        let state = state_from_board(start, &tree, last_attack).unwrap();
+        let mut follow = |_: &Board, choice: &Choice| {
+            choice.consequence().board().to_owned()
+        };
+        let state = state_from_board(
+            start, Vec::new(), &tree, &Ruleset::new(), last_attack, &mut follow,
+        ).unwrap();
         let f_grid = state.board().grid().to_owned();
 
         assert!(s_grid == f_grid);
@@ -438,10 +942,15 @@ mod test {
     fn state_from_board_2x2() -> Result<(), Box<dyn error::Error>> {
         let start = game::canned_2x2_start01();
         let s_grid = start.grid().to_owned();
-        let tree = game::build_tree(start.clone(), 10);
+        let tree = game::build_tree(start.clone(), &Ruleset::new());
         let last_attack = LastAttack::default();
 
-        let state = state_from_board(start, &tree, last_attack).unwrap();
+        let mut follow = |_: &Board, choice: &Choice| {
+            choice.consequence().board().to_owned()
+        };
+        let state = state_from_board(
+            start, Vec::new(), &tree, &Ruleset::new(), last_attack, &mut follow,
+        ).unwrap();
         let f_grid = state.board().grid().to_owned();
 
         assert!(s_grid == f_grid);
@@ -482,4 +991,629 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn scripted_attack_wins() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start02();
+        let player1 = start.players().current();
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_roller(Box::new(ScriptedRoller::new(vec![7, 3])))
+            .session()?;
+
+        let state = session.advance(0)?;
+        let captured = state.board().grid().fetch(Cube::from((1, 0)))?;
+
+        assert!(captured.owner() == player1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scripted_attack_loses() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start02();
+        let player1 = start.players().current();
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_roller(Box::new(ScriptedRoller::new(vec![3, 3])))
+            .session()?;
+
+        let state = session.advance(0)?;
+        let defended = state.board().grid().fetch(Cube::from((1, 0)))?;
+
+        assert!(defended.owner() != player1);
+        let outcome = LastAttack::new(2, 3, 1, 3, false)
+            .with_tie(TieRule::DefenderWins)
+            .with_sides(player1, Player::new(2, 'B'));
+        assert!(state.game() == &Progression::PlayOn(outcome));
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_attack_drops_to_one_die() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start03();
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_ruleset(*Ruleset::new().set_failure_penalty(FailurePenalty::OneDie))
+            .set_roller(Box::new(ScriptedRoller::new(vec![4, 4])))
+            .session()?;
+
+        // Player 'A' is left with no attacks so the turn passes onto player 'B'.
+        let state = session.advance(0)?;
+        let attacker = state.board().grid().fetch(Cube::from((0, 0)))?;
+
+        assert!(attacker.dice() == 1);
+        assert!(state.board().players().current() == Player::new(2, 'B'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tie_goes_to_attacker() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start02();
+        let player1 = start.players().current();
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_tie(TieRule::AttackerWins)
+            .set_roller(Box::new(ScriptedRoller::new(vec![3, 3])))
+            .session()?;
+
+        let state = session.advance(0)?;
+        let captured = state.board().grid().fetch(Cube::from((1, 0)))?;
+
+        assert!(captured.owner() == player1);
+        let outcome = LastAttack::new(2, 3, 1, 3, true)
+            .with_tie(TieRule::AttackerWins)
+            .with_sides(player1, Player::new(2, 'B'));
+        assert!(state.game() == &Progression::PlayOn(outcome));
+        assert!(format!("{}", outcome).ends_with("Tie goes to the attacker."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tie_rerolled() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start02();
+        let player1 = start.players().current();
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_die(Die::D20)
+            .set_tie(TieRule::Reroll)
+            .set_roller(Box::new(ScriptedRoller::new(vec![3, 3, 12, 9])))
+            .session()?;
+
+        let state = session.advance(0)?;
+        let captured = state.board().grid().fetch(Cube::from((1, 0)))?;
+
+        assert!(captured.owner() == player1);
+        let outcome = LastAttack::new(2, 12, 1, 9, true)
+            .with_tie(TieRule::Reroll)
+            .with_sides(player1, Player::new(2, 'B'));
+        assert!(state.game() == &Progression::PlayOn(outcome));
+        assert!(format!("{}", outcome).ends_with("Rolled again after a tie."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn end_turn_early() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start03();
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_ruleset(*Ruleset::new().set_pass(PassRule::Anytime))
+            .session()?;
+
+        let choices = session.current_turn().choices().to_owned();
+        assert!(choices.len() == 3);
+        assert!(*choices[2].action() == Action::Pass);
+
+        // Player 'B' has no attacks thus passes straight back to player 'A'.
+        let state = session.advance(2)?;
+        assert!(state.traversal().len() == 1);
+        assert!(state.traversal()[0].0.players().current() == player2);
+        assert!(state.board().players().current() == player1);
+        assert!(state.game() == &Progression::PlayOn(LastAttack::default()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn manual_placement() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start03();
+        let player1 = Player::new(1, 'A');
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_ruleset(
+                *Ruleset::new()
+                    .set_pass(PassRule::Anytime)
+                    .set_reinforcement(Reinforcement::Captured)
+                    .set_placement(Placement::Manual)
+            )
+            .set_roller(Box::new(ScriptedRoller::new(vec![7, 2])))
+            .session()?;
+
+        // Capture a hex of three dice then end the turn. Player 'A' then has three dice to
+        // place.
+        let state = session.advance(0)?.to_owned();
+        let pass = state
+            .choices()
+            .iter()
+            .position(|choice| *choice.action() == Action::Pass)
+            .unwrap();
+
+        let state = session.advance(pass)?;
+        assert!(*state.board().reinforcing() == 3);
+        assert!(state.board().players().current() == player1);
+        assert!(state
+            .choices()
+            .iter()
+            .all(|choice| match choice.action() {
+                Action::Reinforce(_) => true,
+                _ => false,
+            }));
+
+        let state = session.advance(0)?;
+        assert!(*state.board().reinforcing() == 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reserve_shown_in_state() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let start = game::canned_2x2_start02().with_reserve(player1, 2);
+
+        let session = session::Setup::new()
+            .set_board(start)
+            .session()?;
+
+        let state = session.current_turn();
+        assert!(state.reserves().contains(&(player1, 2)));
+        assert!(state.reserves().contains(&(Player::new(2, 'B'), 0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_applied() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes = vec![
+            (Cube::from((0, 0)), u8::new(player1, 4, true)),
+            (Cube::from((1, 0)), u8::new(player1, 1, true)),
+            (Cube::from((0, 1)), u8::new(player2, 3, true)),
+            (Cube::from((1, 1)), u8::new(player2, 5, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let start = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0);
+        let transfer = Action::Transfer((0, 0).into(), (1, 0).into(), 2);
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_ruleset(*Ruleset::new().set_transfer_limit(1))
+            .session()?;
+
+        let index = session
+            .current_turn()
+            .choices()
+            .iter()
+            .position(|choice| *choice.action() == transfer)
+            .unwrap();
+        let state = session.advance(index)?;
+
+        assert!(state.board().players().current() == player1);
+        assert!(state.board().grid().fetch(Cube::from((0, 0)))?.dice() == 2);
+        assert!(state.board().grid().fetch(Cube::from((1, 0)))?.dice() == 3);
+        assert!(state
+            .choices()
+            .iter()
+            .all(|choice| match choice.action() {
+                Action::Transfer(_, _, _) => false,
+                _ => true,
+            }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_limit_reached() -> Result<(), Box<dyn error::Error>> {
+        let start = game::canned_2x2_start03();
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_ruleset(*Ruleset::new().set_pass(PassRule::Anytime))
+            .set_round_limit(NonZeroUsize::new(1).unwrap(), TieBreaker::MostDice)
+            .session()?;
+        assert!(*session.round() == 1);
+
+        // Player 'A' passes and player 'B' can only pass back. That's the first round.
+        session.advance(2)?;
+        assert!(*session.round() == 2);
+        let state = session.current_turn();
+        let ranking = match state.game() {
+            Progression::GameOverRoundLimit(ranking) => ranking,
+            _ => panic!("Game should be over."),
+        };
+
+        assert!(ranking.winner() == Some(player2));
+        assert!(ranking.players() == vec![player2, player1]);
+        assert!(state.choices().is_empty());
+        assert!(session.advance(0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn team_wins_together() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let hexes = vec![
+            (Cube::from((0, 0)), u8::new(player1, 3, true)),
+            (Cube::from((1, 0)), u8::new(player2, 1, true)),
+            (Cube::from((2, 0)), u8::new(player3, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let start = Board::new(Players::new(3), grid.change_to_rectangle(3, 1), 0, 0);
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_team(player1, 1)
+            .set_team(player3, 1)
+            .set_roller(Box::new(ScriptedRoller::new(vec![10, 2])))
+            .session()?;
+
+        // Only 'B' can be attacked. Taking it wins the game for the team.
+        assert!(session.current_turn().choices().len() == 1);
+        let state = session.advance(0)?;
+        let winner = Winner::Team(vec![player1, player3]);
+
+        assert!(state.game() == &Progression::GameOverWinner(winner.clone()));
+        assert!(format!("{}", winner) == "team A, C");
+
+        Ok(())
+    }
+
+    #[test]
+    fn views_only_offer_own_actions() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+
+        let session = session::Setup::new()
+            .set_board(game::canned_2x2_start02())
+            .session()?;
+
+        let view = session.view_for(player1);
+        assert!(view.actions().len() == session.current_turn().choices().len());
+        assert!(*view.board().viewer() == player1);
+
+        let view = session.view_for(player2);
+        assert!(view.actions().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn views_hide_rolls_from_others() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+
+        let mut session = session::Setup::new()
+            .set_board(game::canned_3x1_start05())
+            .set_roller(Box::new(ScriptedRoller::new(vec![7, 3])))
+            .session()?;
+        let game = session.advance(0)?.game().to_owned();
+
+        let hidden = Progression::PlayOn(LastAttack::default());
+        assert!(game != hidden);
+        // 'A' can't attack and passes so 'B' attacks 'C' with 'A' looking on.
+        assert!(*session.view_for(player2).game() == game);
+        assert!(*session.view_for(player3).game() == game);
+        assert!(*session.view_for(player1).game() == hidden);
+
+        Ok(())
+    }
+
+    #[test]
+    fn wide_holding_session() -> Result<(), Box<dyn error::Error>> {
+        let session: session::Session<u16> = session::Setup::default()
+            .set_players(Players::new(8))
+            .gen_board(4, 4)
+            .session()?;
+
+        let board = session.current_turn().board();
+        assert!(board.grid().len() == 16);
+        assert!(board.grid().iter().all(|ht| ht.data().owner().number() <= &8));
+
+        Ok(())
+    }
+
+    #[test]
+    fn mountain_defender_rolls_extra_die() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes = vec![
+            (Cube::from((0, 0)), u16::new(player1, 3, true)),
+            (Cube::from((1, 0)), u16::new(player2, 2, true)),
+            (Cube::from((0, 1)), u16::new(player2, 1, true)),
+            (Cube::from((1, 1)), u16::new(player2, 1, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let start = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_terrain(Cube::from((1, 0)), Terrain::Mountains);
+
+        let mut session = session::Setup::default()
+            .set_board(start)
+            .set_roller(Box::new(ScriptedRoller::new(vec![5, 9])))
+            .session()?;
+        let index = session
+            .current_turn()
+            .choices()
+            .iter()
+            .position(|choice| match choice.action() {
+                Action::Attack(_, to, _, _) => *to == Cube::from((1, 0)),
+                _ => false,
+            })
+            .unwrap();
+
+        let state = session.advance(index)?;
+        let outcome = match state.game() {
+            Progression::PlayOn(outcome) => format!("{}", outcome),
+            _ => String::new(),
+        };
+        assert!(outcome.starts_with("Defender with 3 dice rolled 9"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn objective_wins_on_points() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let hexes = vec![
+            (Cube::from((0, 0)), u16::new(player1, 2, true)),
+            (Cube::from((1, 0)), u16::new(player2, 5, true)),
+            (Cube::from((0, 1)), u16::new(player2, 5, true)),
+            (Cube::from((1, 1)), u16::new(player2, 5, true)),
+        ];
+        let grid: Grid<u16> = hexes.into_iter().collect();
+        let start = Board::new(Players::new(2), grid.change_to_rectangle(2, 2), 0, 0)
+            .with_objective(Cube::from((0, 0)), true)
+            .with_points(player1, 1);
+
+        let mut ruleset = Ruleset::new();
+        ruleset.set_pass(PassRule::Anytime);
+        let mut session = session::Setup::default()
+            .set_ruleset(ruleset)
+            .set_victory_points(2)
+            .set_board(start)
+            .session()?;
+        let index = session
+            .current_turn()
+            .choices()
+            .iter()
+            .position(|choice| *choice.action() == Action::Pass)
+            .unwrap();
+
+        let state = session.advance(index)?;
+        let winner = Winner::Player(player1);
+        assert!(state.game() == &Progression::GameOverVictoryPoints(winner));
+        assert!(state.board().points(player1) == 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn handicapped_players() {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let mut setup = session::Setup::new();
+        setup.set_handicap(player1, Handicap::new(3, 10, 200));
+        let mut rng = rngs::StdRng::seed_from_u64(7);
+        let grid: Grid<u8> = game::generate_handicapped_grid(
+            6, 6, Players::new(2), 0_f64, setup.ruleset(), &mut rng,
+        );
+        let count = |player: Player| {
+            grid
+                .iter()
+                .filter(|ht| ht.data().owner() == player)
+                .fold((0, 0), |(hexes, dice), ht| (hexes + 1, dice + ht.data().dice()))
+        };
+
+        // Four shares of the hexes against one and ten dice more than the fewest possible.
+        let (hexes, dice) = count(player1);
+        assert!(hexes > count(player2).0);
+        assert!(dice >= hexes + 10);
+
+        // The same seed deals out the same grid.
+        let mut rng = rngs::StdRng::seed_from_u64(7);
+        let again: Grid<u8> = game::generate_handicapped_grid(
+            6, 6, Players::new(2), 0_f64, setup.ruleset(), &mut rng,
+        );
+        assert!(again == grid);
+
+        // Double the reinforcements.
+        let board = game::canned_3x3_start01();
+        let mut ruleset = *setup.ruleset();
+        ruleset.set_reinforcement(Reinforcement::LargestRegion).set_max_dice(7);
+        let passed = game::board_from_pass(&board, &ruleset, &mut |_| 0);
+        let dice: u8 = passed.grid().iter().map(|ht| ht.data().dice()).sum();
+        let before: u8 = board.grid().iter().map(|ht| ht.data().dice()).sum();
+        assert!(dice - before == 10);
+    }
+
+    #[test]
+    fn seeded_boards() {
+        let board = |seed: u64| {
+            session::Setup::new()
+                .set_players(Players::new(3))
+                .set_neutrals(0.2)
+                .set_seed(seed)
+                .gen_board(5, 5)
+                .board()
+                .to_owned()
+        };
+
+        assert!(board(11).is_some());
+        assert!(board(11) == board(11));
+        assert!(board(11) != board(12));
+    }
+
+    #[test]
+    fn resignations() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let start = game::canned_3x1_start05();
+        let hold = |state: &State, cube: (i32, i32)| {
+            *state.board().grid().fetch(Cube::from(cube)).unwrap()
+        };
+
+        // 'A' can't attack so it's straight to 'B' who resigns on their turn.
+        let mut session = session::Setup::new().set_board(start.clone()).session()?;
+        assert!(session.current_turn().board().players().current() == player2);
+        let state = session.resign(player2)?;
+        assert!(state.traversal()[0].1.action() == &Action::Resign(player2));
+        assert!(hold(state, (1, 0)).owner() == Player::neutral());
+        assert!(state.board().players().current() == player3);
+
+        // The last one standing wins.
+        let state = session.resign(player3)?;
+        assert!(state.game() == &Progression::GameOverWinner(Winner::Player(player1)));
+        assert!(session.resign(player1).is_err());
+
+        // Resigning out of turn with frozen hexes.
+        let mut ruleset = Ruleset::new();
+        ruleset.set_resignation(Resignation::Frozen);
+        let mut session = session::Setup::new()
+            .set_ruleset(ruleset)
+            .set_board(start)
+            .session()?;
+        let state = session.resign(player1)?;
+        assert!(state.board().players().current() == player2);
+        assert!(state.board().players().playing() == vec![player2, player3]);
+        assert!(hold(state, (0, 0)).owner() == player1 && !hold(state, (0, 0)).mobile());
+        assert!(session.resign(player1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn final_placings() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+
+        // 'A' is knocked out on the first turn leaving 'B' the winner.
+        let session = session::Setup::new()
+            .set_board(game::canned_3x1_start02())
+            .session()?;
+        let results = session.results().unwrap();
+        assert!(results.placings().placings() == &vec![(player2, 1), (player1, 2)]);
+        assert!(results.eliminations() == &vec![(player1, 1)]);
+
+        // 'B' resigns on turn 2 and 'C' on turn 3.
+        let mut session = session::Setup::new()
+            .set_board(game::canned_3x1_start05())
+            .session()?;
+        session.resign(player2)?;
+        assert!(session.results().is_none());
+        session.resign(player3)?;
+
+        let results = session.results().unwrap();
+        let placings = vec![(player1, 1), (player3, 2), (player2, 3)];
+        assert!(results.placings().placings() == &placings);
+        assert!(results.eliminations() == &vec![(player2, 2), (player3, 3)]);
+        assert!(format!("{}", results.placings()) == "1. A, 2. C, 3. B");
+
+        Ok(())
+    }
+
+    #[test]
+    fn placings_by_conquest() -> Result<(), Box<dyn error::Error>> {
+        let player1 = Player::new(1, 'A');
+        let player2 = Player::new(2, 'B');
+        let player3 = Player::new(3, 'C');
+        let hexes = vec![
+            (Cube::from((0, 0)), u8::new(player1, 3, true)),
+            (Cube::from((1, 0)), u8::new(player3, 1, true)),
+            (Cube::from((2, 0)), u8::new(player2, 1, true)),
+        ];
+        let grid: Grid<u8> = hexes.into_iter().collect();
+        let start = Board::new(Players::new(3), grid.change_to_rectangle(3, 1), 0, 0);
+
+        let mut session = session::Setup::new()
+            .set_board(start)
+            .set_roller(Box::new(ScriptedRoller::new(vec![10, 2, 10, 2])))
+            .session()?;
+
+        // 'C' goes out to the first attack and 'B' to the second which ends the game.
+        session.advance(0)?;
+        assert!(session.current_turn().board().players().eliminated() == vec![player3]);
+        assert!(session.results().is_none());
+        let state = session.advance(0)?;
+        assert!(state.game() == &Progression::GameOverWinner(Winner::Player(player1)));
+
+        let results = session.results().unwrap();
+        let placings = vec![(player1, 1), (player2, 2), (player3, 3)];
+        assert!(results.placings().placings() == &placings);
+        assert!(results.eliminations() == &vec![(player3, 1), (player2, 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn neutrals_seeded() {
+        let mut setup = session::Setup::new();
+        let board = setup.set_neutrals(1_f64).gen_board(3, 3).board().clone().unwrap();
+        assert!(board.grid().iter().all(|ht| ht.data().owner().is_neutral()));
+
+        let board = setup.set_neutrals(0_f64).gen_board(3, 3).board().clone().unwrap();
+        assert!(board.grid().iter().all(|ht| !ht.data().owner().is_neutral()));
+    }
+
+    #[test]
+    fn too_many_players_for_holding() {
+        let mut setup: Setup<u8> = Setup::new();
+        setup.set_players(Players::new(8)).gen_board(3, 3);
+        assert!(setup.board().is_none());
+        assert!(setup.session().is_err());
+
+        let mut setup: Setup<u16> = Setup::default();
+        setup.set_players(Players::new(8)).gen_board(3, 3);
+        assert!(setup.board().is_some());
+    }
+
+    #[test]
+    fn same_seed_same_game() -> Result<(), Box<dyn error::Error>> {
+        let mut session1 = session::Setup::new()
+            .set_board(game::canned_2x2_start02())
+            .set_seed(1234)
+            .session()?;
+        let mut session2 = session::Setup::new()
+            .set_board(game::canned_2x2_start02())
+            .set_seed(1234)
+            .session()?;
+
+        let state1 = session1.advance(0)?.to_owned();
+        let state2 = session2.advance(0)?.to_owned();
+
+        assert!(state1.game() == state2.game());
+        assert!(state1.board() == state2.board());
+
+        Ok(())
+    }
 }
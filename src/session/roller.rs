@@ -0,0 +1,158 @@
+//! Dice rolling for a `Session`. Kept behind a trait so that games can be reproduced from
+//! a seed and tests can script the exact rolls they need.
+use std::collections::VecDeque;
+use std::fmt;
+
+use rand::{rngs, Rng, SeedableRng};
+
+fn roll_dice<T: Rng>(dice: u8, faces: u8, random: &mut T) -> usize {
+    (0..dice)
+        .fold(0, |sum, _| -> usize {
+            sum + random.gen_range(1, faces as usize + 1)
+        })
+}
+
+/// Anything that can roll a handful of dice for a `Session`.
+pub trait DiceRoller: fmt::Debug {
+    /// Roll `dice` amount of dice with `faces` sides each and return the summed total.
+    fn roll(&mut self, dice: u8, faces: u8) -> usize;
+
+    /// Pick an index below `choices`. Used for randomly placing reinforcements.
+    fn pick(&mut self, choices: usize) -> usize;
+
+    /// Trait objects can't be `Clone`. This works around it so a `Session` still can be.
+    fn boxed_clone(&self) -> Box<dyn DiceRoller>;
+}
+
+impl Clone for Box<dyn DiceRoller> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+/// The default roller. Two rollers built from the same seed produce the same rolls which
+/// allows a game to be replayed.
+#[derive(Debug, Clone)]
+pub struct SeededRoller {
+    seed: u64,
+    rng: rngs::StdRng,
+}
+
+impl SeededRoller {
+    pub fn new(seed: u64) -> Self {
+        SeededRoller {
+            seed,
+            rng: rngs::StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Use a random seed. The seed can still be fetched afterwards for a replay.
+    pub fn from_entropy() -> Self {
+        SeededRoller::new(rand::thread_rng().gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl DiceRoller for SeededRoller {
+    fn roll(&mut self, dice: u8, faces: u8) -> usize {
+        roll_dice(dice, faces, &mut self.rng)
+    }
+
+    fn pick(&mut self, choices: usize) -> usize {
+        self.rng.gen_range(0, choices)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn DiceRoller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Returns predetermined roll totals in order regardless of how many dice are rolled.
+/// Meant for tests. Will panic once the script runs out.
+#[derive(Debug, Clone)]
+pub struct ScriptedRoller {
+    rolls: VecDeque<usize>,
+}
+
+impl ScriptedRoller {
+    pub fn new(rolls: Vec<usize>) -> Self {
+        ScriptedRoller { rolls: rolls.into() }
+    }
+
+    /// How many rolls are left in the script.
+    pub fn remaining(&self) -> usize {
+        self.rolls.len()
+    }
+}
+
+impl DiceRoller for ScriptedRoller {
+    fn roll(&mut self, _dice: u8, _faces: u8) -> usize {
+        self.rolls
+            .pop_front()
+            .expect("Scripted rolls exhausted.")
+    }
+
+    /// Picks come off the same script as the rolls.
+    fn pick(&mut self, choices: usize) -> usize {
+        self.rolls
+            .pop_front()
+            .expect("Scripted rolls exhausted.") % choices
+    }
+
+    fn boxed_clone(&self) -> Box<dyn DiceRoller> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let mut roller1 = SeededRoller::new(42);
+        let mut roller2 = SeededRoller::new(42);
+
+        for dice in 1..6 {
+            assert!(roller1.roll(dice, 6) == roller2.roll(dice, 6));
+        }
+    }
+
+    #[test]
+    fn rolls_within_bounds() {
+        let mut roller = SeededRoller::new(7);
+
+        for _ in 0..100 {
+            let rolled = roller.roll(3, 6);
+            assert!(rolled >= 3 && rolled <= 18);
+
+            let rolled = roller.roll(2, 20);
+            assert!(rolled >= 2 && rolled <= 40);
+        }
+    }
+
+    #[test]
+    fn scripted_rolls_in_order() {
+        let mut roller = ScriptedRoller::new(vec![4, 9, 2]);
+
+        assert!(roller.roll(1, 6) == 4);
+        assert!(roller.roll(5, 6) == 9);
+        assert!(roller.remaining() == 1);
+        assert!(roller.roll(2, 6) == 2);
+        assert!(roller.remaining() == 0);
+    }
+
+    #[test]
+    fn picks_within_bounds() {
+        let mut roller = SeededRoller::new(7);
+        let mut scripted = ScriptedRoller::new(vec![5]);
+
+        for _ in 0..100 {
+            assert!(roller.pick(3) < 3);
+        }
+        assert!(scripted.pick(3) == 2);
+    }
+}